@@ -0,0 +1,125 @@
+use crate::state::{CommitRecord, State, UnlockedAchievement, read_achievements};
+use crate::theme::{bold, dim};
+use anyhow::Result;
+use chrono::{Local, NaiveDate};
+use std::collections::BTreeSet;
+
+pub struct Achievement {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Whether the newest commit in the history unlocks the achievement
+    unlocked_by: fn(commit: &CommitRecord, history: &[CommitRecord]) -> bool,
+}
+
+pub static ACHIEVEMENTS: [Achievement; 5] = [
+    Achievement {
+        id: "pioneer",
+        name: "Pioneer",
+        description: "Earn XP in a new repository",
+        unlocked_by: |commit, history| {
+            history
+                .iter()
+                .filter(|r| r.repo_id == commit.repo_id)
+                .count()
+                == 1
+        },
+    },
+    Achievement {
+        id: "spring_cleaning",
+        name: "Spring Cleaning",
+        description: "Delete 1,000 lines in a single commit",
+        unlocked_by: |commit, _| commit.lines_deleted >= 1000,
+    },
+    Achievement {
+        id: "unstoppable",
+        name: "Unstoppable",
+        description: "Commit every day for 30 days in a row",
+        unlocked_by: |commit, history| consecutive_days(commit, history) >= 30,
+    },
+    Achievement {
+        id: "storyteller",
+        name: "Storyteller",
+        description: "Write 100 commit messages with a body",
+        unlocked_by: |_, history| history.iter().filter(|r| r.has_body).count() >= 100,
+    },
+    Achievement {
+        id: "peacemaker",
+        name: "Peacemaker",
+        description: "Resolve a merge conflict",
+        unlocked_by: |commit, _| commit.resolved_conflicts,
+    },
+];
+
+pub fn find(id: &str) -> Option<&'static Achievement> {
+    ACHIEVEMENTS.iter().find(|a| a.id == id)
+}
+
+/// Unlock every achievement the most recently recorded commit earned
+pub fn evaluate(state: &mut State, commit: &CommitRecord) -> Vec<&'static Achievement> {
+    let earned: Vec<&'static Achievement> = ACHIEVEMENTS
+        .iter()
+        .filter(|a| !state.achievements.iter().any(|u| u.id == a.id))
+        .filter(|a| (a.unlocked_by)(commit, &state.history))
+        .collect();
+    let unlocked_at = Local::now().timestamp();
+    state
+        .achievements
+        .extend(earned.iter().map(|a| UnlockedAchievement {
+            id: a.id.to_string(),
+            unlocked_at,
+        }));
+    earned
+}
+
+pub fn announce(achievements: &[&Achievement]) {
+    for achievement in achievements {
+        println!(
+            "🏆 Achievement unlocked: {} - {}",
+            bold(achievement.name),
+            achievement.description
+        );
+    }
+}
+
+/// List every achievement and when it was unlocked
+pub fn list_achievements() -> Result<()> {
+    let unlocked = read_achievements()?;
+    for achievement in &ACHIEVEMENTS {
+        match unlocked.iter().find(|u| u.id == achievement.id) {
+            Some(u) => {
+                println!(
+                    "🏆 {} {:<42} {}",
+                    bold(&format!("{:<16}", achievement.name)),
+                    achievement.description,
+                    u.local_date()
+                );
+            }
+            None => println!(
+                "   {}",
+                dim(&format!(
+                    "{:<16} {:<42} locked",
+                    achievement.name, achievement.description
+                ))
+            ),
+        }
+    }
+    println!();
+    println!("{}/{} unlocked", unlocked.len(), ACHIEVEMENTS.len());
+    Ok(())
+}
+
+/// Days in a row with at least one commit, ending on the day of the given commit
+fn consecutive_days(commit: &CommitRecord, history: &[CommitRecord]) -> u32 {
+    let days: BTreeSet<NaiveDate> = history.iter().map(|r| r.local_date()).collect();
+    let mut day = commit.local_date();
+    let mut streak = 0;
+    while days.contains(&day) {
+        streak += 1;
+        match day.pred_opt() {
+            Some(previous) => day = previous,
+            None => break,
+        }
+    }
+    streak
+}
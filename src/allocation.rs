@@ -0,0 +1,113 @@
+use crate::scaling::XpType;
+use anyhow::{Result, anyhow};
+use bincode::{Decode, Encode};
+use clap::ValueEnum;
+use std::fmt;
+
+/// How XP is split between the stats being leveled, in percent
+#[derive(Encode, Decode, Debug, Clone, PartialEq)]
+pub struct Allocation {
+    shares: Vec<(XpType, u32)>,
+}
+
+impl Allocation {
+    /// Put all XP into one stat
+    pub fn single(stat: XpType) -> Self {
+        Allocation {
+            shares: vec![(stat, 100)],
+        }
+    }
+
+    pub fn new(shares: Vec<(XpType, u32)>) -> Result<Self> {
+        if shares.is_empty() {
+            return Err(anyhow!("Choose at least one stat to level"));
+        }
+        for (i, (stat, percent)) in shares.iter().enumerate() {
+            if *stat == XpType::Total {
+                return Err(anyhow!("Cannot level Total directly"));
+            }
+            if *percent == 0 {
+                return Err(anyhow!("{stat:?} must get more than 0%"));
+            }
+            if shares[..i].iter().any(|(s, _)| s == stat) {
+                return Err(anyhow!("{stat:?} is listed more than once"));
+            }
+        }
+        let sum: u32 = shares.iter().map(|(_, percent)| percent).sum();
+        if sum != 100 {
+            return Err(anyhow!("Shares must add up to 100%, not {sum}%"));
+        }
+        Ok(Allocation { shares })
+    }
+
+    pub fn shares(&self) -> &[(XpType, u32)] {
+        &self.shares
+    }
+
+    /// Divide an amount of XP between the stats, rounding leftovers into the largest share
+    pub fn split(&self, amt: u128) -> Vec<(XpType, u128)> {
+        let mut split: Vec<(XpType, u128)> = self
+            .shares
+            .iter()
+            .map(|(stat, percent)| {
+                // Split hundreds and the remainder apart so no amount can overflow
+                let percent = *percent as u128;
+                (*stat, amt / 100 * percent + amt % 100 * percent / 100)
+            })
+            .collect();
+        let leftover = amt - split.iter().map(|(_, xp)| xp).sum::<u128>();
+        let largest = self
+            .shares
+            .iter()
+            .enumerate()
+            .max_by_key(|(i, (_, percent))| (*percent, std::cmp::Reverse(*i)))
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        if let Some((_, xp)) = split.get_mut(largest) {
+            *xp += leftover;
+        }
+        split
+    }
+
+    /// XP a stat receives from an amount, all of it for Total
+    pub fn xp_for(&self, amt: u128, stat: XpType) -> u128 {
+        if stat == XpType::Total {
+            return amt;
+        }
+        self.split(amt)
+            .into_iter()
+            .filter(|(s, _)| *s == stat)
+            .map(|(_, xp)| xp)
+            .sum()
+    }
+}
+
+impl fmt::Display for Allocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let [(stat, 100)] = self.shares.as_slice() {
+            return write!(f, "{stat:?}");
+        }
+        let shares: Vec<String> = self
+            .shares
+            .iter()
+            .map(|(stat, percent)| format!("{stat:?} {percent}%"))
+            .collect();
+        write!(f, "{}", shares.join(", "))
+    }
+}
+
+/// Parse `stat=percent`, or a bare stat for all of the XP
+pub fn parse_share(s: &str) -> Result<(XpType, u32), String> {
+    let (stat, percent) = match s.split_once('=') {
+        Some((stat, percent)) => (
+            stat,
+            percent
+                .trim_end_matches('%')
+                .parse()
+                .map_err(|_| format!("invalid percentage `{percent}`"))?,
+        ),
+        None => (s, 100),
+    };
+    let stat = XpType::from_str(stat.trim(), true)?;
+    Ok((stat, percent))
+}
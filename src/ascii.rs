@@ -1,6 +1,10 @@
+/// More stars than this no longer fit next to the level
+const MAX_STARS: u32 = 5;
+
 pub static ASCII_NUMBERS: [&str; 10] = [ZERO, ONE, TWO, THREE, FOUR, FIVE, SIX, SEVEN, EIGHT, NINE];
 
-pub fn display_number_x(number: u32) -> String {
+/// Big level number followed by an x and a star for each prestige
+pub fn display_number_x(number: u32, prestige: u32) -> String {
     // number -> string -> vector of chars -> char to digit -> index lookup
     let mut digits = number
         .to_string()
@@ -11,6 +15,9 @@ pub fn display_number_x(number: u32) -> String {
         })
         .collect::<Vec<_>>();
     digits.push(X);
+    for _ in 0..prestige.min(MAX_STARS) {
+        digits.push(STAR);
+    }
     display_side_by_side(&digits, 0)
 }
 
@@ -153,6 +160,14 @@ pub static X: &str = r#"
 ▝▀ ▀▘
 "#;
 
+pub static STAR: &str = r#"
+
+  ▄
+▄▟█▙▄
+ ███
+▟▛ ▜▙
+"#;
+
 pub static BANNER: &str = r#"
   ▄▄  ▄▄▄ ▗▄▄▄▖       ▄   ▗▄▖   ▄▄ ▗▄▄▄▖▗▄ ▗▖ ▗▄▖  ▄▄▄  ▗▄▖ ▗▄ ▗▖
  █▀▀▌ ▀█▀ ▝▀█▀▘      ▐█▌ ▗▛▀▜  █▀▀▌▐▛▀▀▘▐█ ▐▌▗▛▀▜  ▀█▀  █▀█ ▐█ ▐▌
@@ -0,0 +1,407 @@
+use crate::achievements::{self, Achievement};
+use crate::allocation::Allocation;
+use crate::caps::{self, Cap};
+use crate::config::config;
+use crate::git::GitRepo;
+use crate::levelup;
+use crate::output::{LevelReport, SCHEMA_VERSION, ShareReport, allocation_report};
+use crate::progress::{animated_progress_bar, format_progress_bar};
+use crate::quests;
+use crate::scaling::{XpGain, XpType, calculate_level_info, total_xp_gain};
+use crate::setup::{first_run, welcome_message};
+use crate::state::{
+    CommitRecord, Experience, PendingAward, State, StateLock, XpBreakdown, add_pending, lock_state,
+    read_xp, repos, take_pending,
+};
+use crate::theme::{bold, dim};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+
+/// Commits explained after an award, older ones are only counted
+const BREAKDOWN_COMMITS: usize = 5;
+
+pub struct Award {
+    /// Commits awarded, oldest first
+    pub commits: Vec<CommitAward>,
+    pub pre_exp: Experience,
+    pub post_exp: Experience,
+    /// Stats new XP goes into
+    pub allocation: Allocation,
+    pub achievements: Vec<&'static Achievement>,
+    /// Keys of quests completed by these commits
+    pub quests: Vec<String>,
+    /// Caps that reduced the XP of these commits
+    pub caps: Vec<Cap>,
+}
+
+/// A commit and how its XP was calculated
+pub struct CommitAward {
+    pub record: CommitRecord,
+    pub gain: XpGain,
+}
+
+impl Award {
+    pub fn xp_gained(&self) -> u128 {
+        self.post_exp.total - self.pre_exp.total
+    }
+
+    pub fn report(&self) -> AwardReport {
+        AwardReport {
+            version: SCHEMA_VERSION,
+            commits: self.commits.len(),
+            xp_gained: self.xp_gained(),
+            total: LevelReport::new(self.post_exp.total, XpType::Total),
+            output: LevelReport::new(self.post_exp.output, XpType::Output),
+            pedantry: LevelReport::new(self.post_exp.pedantry, XpType::Pedantry),
+            precision: LevelReport::new(self.post_exp.precision, XpType::Precision),
+            knowledge: LevelReport::new(self.post_exp.knowledge, XpType::Knowledge),
+            active: allocation_report(&self.allocation),
+            achievements: self.achievements.iter().map(|a| a.id).collect(),
+            quests: self.quests.clone(),
+            caps: self.caps.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AwardReport {
+    version: u32,
+    commits: usize,
+    xp_gained: u128,
+    /// Levels after the award
+    total: LevelReport,
+    output: LevelReport,
+    pedantry: LevelReport,
+    precision: LevelReport,
+    knowledge: LevelReport,
+    /// Stats new XP goes into
+    active: Vec<ShareReport>,
+    /// Ids of achievements unlocked
+    achievements: Vec<&'static str>,
+    /// Keys of quests completed
+    quests: Vec<String>,
+    caps: Vec<Cap>,
+}
+
+/// Collect XP for every commit made in the repository since the last recorded one
+pub fn award_repo(lock: &StateLock, repo_path: &str) -> Result<Award> {
+    let repo = GitRepo::new(repo_path)?;
+    let repo_id = repo.id()?;
+    // Every commit is applied to one copy of the state, saved once at the end
+    let mut state = State::load(lock)?;
+    let workdir = repo.workdir()?;
+    let repo_state = state.repo_mut(&repo_id)?;
+    let moved = repo_state.path.as_deref() != Some(workdir.as_str());
+    repo_state.path = Some(workdir);
+    let stats = repo.commits_since(&repo_state.last_recorded_commit)?;
+    let pre_exp = state.experience.clone();
+    let mut achievements = Vec::new();
+    let mut quests = Vec::new();
+    let mut caps = Vec::new();
+    let mut commits = Vec::new();
+    if !stats.is_empty() {
+        let policy = config().policy.with_repo(&repo)?;
+        let scored = policy.score(&stats)?;
+        let allocation = state.allocation.clone();
+        // Award the oldest commit first so the history stays in order
+        for (commit, score) in stats.iter().zip(&scored).rev() {
+            let mut record = CommitRecord {
+                sha: commit.sha.clone(),
+                repo_id: repo_id.clone(),
+                timestamp: commit.timestamp,
+                offset_minutes: commit.offset_minutes,
+                lines_added: score.lines_added,
+                lines_deleted: score.lines_deleted,
+                message_len: score.message_len,
+                has_body: has_body(&commit.message),
+                touched_tests: commit.files.iter().any(|f| is_test_file(&f.path)),
+                resolved_conflicts: commit.resolved_conflicts,
+                xp: 0,
+                breakdown: XpBreakdown::default(),
+                allocation: allocation.clone(),
+            };
+            let gain = total_xp_gain(
+                score.lines_added,
+                score.lines_deleted,
+                score.message_len,
+                record.local_date(),
+                &state,
+                &policy,
+            );
+            record.xp = gain.xp;
+            record.breakdown = gain.breakdown.clone();
+            for cap in &gain.caps {
+                if !caps.contains(cap) {
+                    caps.push(*cap);
+                }
+            }
+            state.add_xp(record.xp);
+            state.history.push(record.clone());
+            achievements.extend(achievements::evaluate(&mut state, &record));
+            commits.push(CommitAward { record, gain });
+        }
+        state.repo_mut(&repo_id)?.last_recorded_commit = stats[0].sha.clone();
+        quests = quests::evaluate(&mut state);
+    }
+    if moved || !commits.is_empty() {
+        state.save(lock)?;
+    }
+    Ok(Award {
+        commits,
+        pre_exp,
+        post_exp: state.experience,
+        allocation: state.allocation,
+        achievements,
+        quests,
+        caps,
+    })
+}
+
+/// Whether a commit message has more than a subject line
+fn has_body(message: &str) -> bool {
+    message
+        .trim()
+        .lines()
+        .skip(1)
+        .any(|line| !line.trim().is_empty())
+}
+
+/// Whether a file is a test, by living in a test directory or being named like
+/// `test_x.py`, `x_test.go`, `x_spec.rb`, `XTest.java`, `x.test.ts` or `x.spec.js`
+fn is_test_file(path: &str) -> bool {
+    let mut components: Vec<&str> = path.split('/').collect();
+    let name = components.pop().unwrap_or_default();
+    let in_test_dir = components.iter().any(|dir| {
+        matches!(
+            dir.to_lowercase().as_str(),
+            "test" | "tests" | "spec" | "specs" | "__tests__"
+        )
+    });
+    let parts: Vec<&str> = name.split('.').collect();
+    let stem = parts[0];
+    let lower = stem.to_lowercase();
+    // Every part between the stem and the extension, `test` in `x.test.ts`
+    let middle = parts.get(1..parts.len() - 1).unwrap_or_default();
+    in_test_dir
+        || lower.starts_with("test_")
+        || lower.ends_with("_test")
+        || lower.ends_with("_spec")
+        || stem.ends_with("Test")
+        || stem.ends_with("Tests")
+        || middle
+            .iter()
+            .any(|part| matches!(part.to_lowercase().as_str(), "test" | "spec"))
+}
+
+/// Collect XP from every registered repository without relying on hooks
+pub fn sync() -> Result<()> {
+    if first_run() {
+        welcome_message();
+        return Ok(());
+    }
+    let mut repos: Vec<_> = repos()?.into_iter().collect();
+    repos.sort_by(|a, b| a.1.path.cmp(&b.1.path));
+    println!("Syncing {} repositories", repos.len());
+
+    let mut total_commits = 0;
+    let mut total_xp = 0;
+    let before = read_xp()?;
+    for (repo_id, repo) in repos {
+        let Some(path) = repo.path else {
+            println!(
+                "  {}: no known path, run `git ascend` inside it once to record one",
+                &repo_id[..repo_id.len().min(8)]
+            );
+            continue;
+        };
+        // Lock each repository on its own so hooks are not kept waiting for the whole sync
+        let award = award_repo(&lock_state()?, &path);
+        match award {
+            Ok(award) => {
+                println!(
+                    "  {path}: {} commits, +{} XP",
+                    award.commits.len(),
+                    award.xp_gained()
+                );
+                achievements::announce(&award.achievements);
+                quests::announce(&award.quests);
+                caps::announce(&award.caps);
+                total_commits += award.commits.len();
+                total_xp += award.xp_gained();
+            }
+            Err(e) => println!("  {path}: skipped ({e})"),
+        }
+    }
+
+    println!("Collected {total_xp} XP from {total_commits} commits");
+    let after = read_xp()?;
+    display_award(after.total, after.total, false);
+    levelup::announce(&before, &after, false);
+    Ok(())
+}
+
+/// Explain where each commit's XP came from and which stats received it
+pub fn display_breakdown(commits: &[CommitAward]) {
+    let skipped = commits.len().saturating_sub(BREAKDOWN_COMMITS);
+    if skipped > 0 {
+        println!("{}", dim(&format!("...and {skipped} earlier commits")));
+    }
+    for CommitAward { record, gain } in &commits[skipped..] {
+        let split = record.allocation.split(record.xp);
+        let receivers: Vec<String> = match split.as_slice() {
+            [(stat, _)] => vec![format!("{stat:?}")],
+            shares => shares
+                .iter()
+                .map(|(stat, xp)| format!("{stat:?} +{xp}"))
+                .collect(),
+        };
+        println!(
+            "{} +{} XP to {}",
+            bold(&record.sha.chars().take(7).collect::<String>()),
+            record.xp,
+            receivers.join(", ")
+        );
+        let mut why = format!(
+            "  (Output {:.1} for +{} lines + Pedantry {:.1} for -{} lines + Precision {:.1} for a {} character message) × Knowledge {:.2}x",
+            gain.breakdown.output,
+            record.lines_added,
+            gain.breakdown.pedantry,
+            record.lines_deleted,
+            gain.breakdown.precision,
+            record.message_len,
+            gain.knowledge
+        );
+        if gain.streak != 1.0 {
+            why.push_str(&format!(" × streak {:.2}x", gain.streak));
+        }
+        if gain.prestige != 1.0 {
+            why.push_str(&format!(" × prestige {:.2}x", gain.prestige));
+        }
+        println!("{}", dim(&why));
+    }
+}
+
+/// Show the Total progress bar moving from one XP amount to another
+pub fn display_award(from: u128, to: u128, animate: bool) {
+    if animate {
+        animated_progress_bar(from, to, None, XpType::Total, |total_xp| {
+            let info = calculate_level_info(total_xp, XpType::Total);
+            (
+                info.current_level_progress,
+                info.xp_needed_to_level,
+                info.level,
+            )
+        });
+    } else {
+        let info = calculate_level_info(to, XpType::Total);
+        let cur_bar = format_progress_bar(
+            info.current_level_progress,
+            info.xp_needed_to_level,
+            None,
+            None,
+            Some(XpType::Total),
+        );
+        print!("{}x {}", info.level, cur_bar);
+    }
+    println!();
+}
+
+/// Hand the award off to a detached process so the commit returns immediately
+pub fn award_in_background(repo_path: &str) -> Result<()> {
+    let repo_path = fs::canonicalize(repo_path).context("Could not resolve repository path")?;
+    Command::new(env::current_exe()?)
+        .arg("--repo-path")
+        .arg(repo_path)
+        .arg("--record-pending")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()
+        .context("Could not start background process")?;
+    Ok(())
+}
+
+/// Award the repository and keep the result until the next interactive run
+pub fn record_pending(repo_path: &str) -> Result<()> {
+    let lock = lock_state()?;
+    let award = award_repo(&lock, repo_path)?;
+    if !award.commits.is_empty() {
+        add_pending(
+            &lock,
+            PendingAward {
+                commits: award.commits.len() as u32,
+                gained: award.post_exp.saturating_sub(&award.pre_exp),
+                achievements: award
+                    .achievements
+                    .iter()
+                    .map(|a| a.id.to_string())
+                    .collect(),
+                quests: award.quests,
+                caps: award.caps,
+            },
+        )?;
+    }
+    Ok(())
+}
+
+/// Show XP that was collected in the background since the last interactive run
+pub fn show_pending(animate: bool) -> Result<()> {
+    let (pending, now) = {
+        let lock = lock_state()?;
+        (take_pending(&lock)?, read_xp()?)
+    };
+    if let Some(pending) = pending {
+        println!(
+            "While you were away: {} commits, +{} XP",
+            pending.commits, pending.gained.total
+        );
+        let before = now.saturating_sub(&pending.gained);
+        display_award(before.total, now.total, animate);
+        levelup::announce(&before, &now, animate);
+        let achievements: Vec<_> = pending
+            .achievements
+            .iter()
+            .filter_map(|id| achievements::find(id))
+            .collect();
+        achievements::announce(&achievements);
+        quests::announce(&pending.quests);
+        caps::announce(&pending.caps);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_test_files() {
+        for path in [
+            "tests/award.rs",
+            "src/__tests__/app.js",
+            "spec/models/user_spec.rb",
+            "test_scaling.py",
+            "pkg/award_test.go",
+            "src/AwardTest.java",
+            "web/app.test.ts",
+            "web/app.spec.js",
+        ] {
+            assert!(is_test_file(path), "{path}");
+        }
+        for path in [
+            "src/latest.rs",
+            "contest/main.c",
+            "src/inspect.rs",
+            "special/mod.rs",
+            "src/testing.rs",
+            "Cargo.toml",
+        ] {
+            assert!(!is_test_file(path), "{path}");
+        }
+    }
+}
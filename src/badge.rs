@@ -0,0 +1,168 @@
+use crate::output::LevelReport;
+use crate::scaling::{SUB_STATS, XpType};
+use crate::stats::{StatsReport, stats_report};
+use crate::theme::bar_color;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::fs;
+use std::path::Path;
+
+/// Width of a character in the badge font, close enough to size text without measuring it
+const CHAR_WIDTH: usize = 7;
+/// Width of the Total bar on the cards
+const TOTAL_BAR_WIDTH: u128 = 360;
+/// Width of the sub stat bars on the cards
+const BAR_WIDTH: u128 = 180;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum BadgeTemplate {
+    /// Small badge in the style of shields.io
+    Flat,
+    /// Light card with the level and every stat
+    Card,
+    /// Dark card with the level and every stat
+    Dark,
+}
+
+/// Colours of a card's background, text and empty bar
+struct Palette {
+    background: &'static str,
+    border: &'static str,
+    text: &'static str,
+    muted: &'static str,
+    track: &'static str,
+}
+
+const LIGHT: Palette = Palette {
+    background: "#ffffff",
+    border: "#d0d7de",
+    text: "#1f2328",
+    muted: "#656d76",
+    track: "#eaeef2",
+};
+
+const DARK: Palette = Palette {
+    background: "#0d1117",
+    border: "#30363d",
+    text: "#e6edf3",
+    muted: "#8d96a0",
+    track: "#21262d",
+};
+
+/// Write a self-contained SVG of the current levels, `-` prints it instead
+pub fn badge(out: &Path, template: BadgeTemplate) -> Result<()> {
+    let stats = stats_report()?;
+    let svg = match template {
+        BadgeTemplate::Flat => flat(&stats),
+        BadgeTemplate::Card => card(&stats, &LIGHT),
+        BadgeTemplate::Dark => card(&stats, &DARK),
+    };
+    if out == Path::new("-") {
+        print!("{svg}");
+        return Ok(());
+    }
+    fs::write(out, svg).with_context(|| format!("Could not write {}", out.display()))?;
+    println!("Badge written to {}", out.display());
+    Ok(())
+}
+
+/// Colour of a stat's bar, falling back to a fixed colour when the theme leaves it uncoloured
+pub fn stat_color(stat: XpType) -> &'static str {
+    bar_color(stat).hex().unwrap_or(match stat {
+        XpType::Total => "#8250df",
+        XpType::Output => "#1a7f37",
+        XpType::Pedantry => "#cf222e",
+        XpType::Precision => "#bf8700",
+        XpType::Knowledge => "#0969da",
+    })
+}
+
+fn developer(stats: &StatsReport) -> String {
+    let stars = "★".repeat(stats.prestige.count as usize);
+    format!("{}x developer{}", stats.total.level, stars)
+}
+
+fn flat(stats: &StatsReport) -> String {
+    let label = "git ascend";
+    let value = developer(stats);
+    let label_width = label.chars().count() * CHAR_WIDTH + 12;
+    let value_width = value.chars().count() * CHAR_WIDTH + 12;
+    let width = label_width + value_width;
+    let color = stat_color(XpType::Total);
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{label}: {value}">
+  <title>{label}: {value}</title>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{value_width}" height="20" fill="{color}"/>
+  </g>
+  <g fill="#fff" font-family="Verdana,DejaVu Sans,sans-serif" font-size="11" text-anchor="middle">
+    <text x="{}" y="14">{label}</text>
+    <text x="{}" y="14">{value}</text>
+  </g>
+</svg>
+"##,
+        label_width / 2,
+        label_width + value_width / 2,
+    )
+}
+
+fn card(stats: &StatsReport, palette: &Palette) -> String {
+    let height = 110 + SUB_STATS.len() * 26;
+    let mut svg = format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="400" height="{height}" role="img" aria-label="{developer}">
+  <title>{developer}</title>
+  <rect x="0.5" y="0.5" width="399" height="{}" rx="6" fill="{}" stroke="{}"/>
+  <g font-family="Verdana,DejaVu Sans,sans-serif" fill="{}">
+    <text x="20" y="40" font-size="22" font-weight="bold">{developer}</text>
+    <text x="20" y="62" font-size="11" fill="{}">Prestige {} ({:.2}x) · Streak {} days ({:.2}x)</text>
+"##,
+        height - 1,
+        palette.background,
+        palette.border,
+        palette.text,
+        palette.muted,
+        stats.prestige.count,
+        stats.prestige.multiplier,
+        stats.streak.current,
+        stats.streak.multiplier,
+        developer = developer(stats),
+    );
+    svg.push_str(&bar(20, 76, &stats.total, XpType::Total, palette));
+    for (i, stat) in SUB_STATS.into_iter().enumerate() {
+        let y = 110 + i * 26;
+        let level = stats.level(stat);
+        svg.push_str(&format!(
+            "    <text x=\"20\" y=\"{}\" font-size=\"12\">{stat:?} {}</text>\n",
+            y + 10,
+            level.level
+        ));
+        svg.push_str(&bar(130, y, level, stat, palette));
+        svg.push_str(&format!(
+            "    <text x=\"380\" y=\"{}\" font-size=\"12\" text-anchor=\"end\">{:.2}x</text>\n",
+            y + 10,
+            level.multiplier.unwrap_or(1.0)
+        ));
+    }
+    svg.push_str("  </g>\n</svg>\n");
+    svg
+}
+
+/// Progress towards a stat's next level
+fn bar(x: usize, y: usize, level: &LevelReport, stat: XpType, palette: &Palette) -> String {
+    let width = if stat == XpType::Total {
+        TOTAL_BAR_WIDTH
+    } else {
+        BAR_WIDTH
+    };
+    let filled = (level.progress * width)
+        .checked_div(level.needed)
+        .unwrap_or(0)
+        .min(width);
+    format!(
+        "    <rect x=\"{x}\" y=\"{y}\" width=\"{width}\" height=\"12\" rx=\"3\" fill=\"{}\"/>\n    <rect x=\"{x}\" y=\"{y}\" width=\"{filled}\" height=\"12\" rx=\"3\" fill=\"{}\"/>\n",
+        palette.track,
+        stat_color(stat)
+    )
+}
@@ -0,0 +1,139 @@
+use crate::config::config;
+use crate::scaling::XpType;
+use crate::state::{CommitRecord, XpBreakdown};
+use bincode::{Decode, Encode};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Limits on how much XP a single stat can earn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StatCap {
+    /// Amount in one commit after which more only counts logarithmically, 0 disables
+    pub threshold: u32,
+    /// XP the stat earns per day before the rest is reduced, 0 disables
+    pub daily: u64,
+    /// Fraction of XP kept once the daily cap is reached
+    pub beyond_daily: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CapsConfig {
+    /// Threshold counts lines added
+    pub output: StatCap,
+    /// Threshold counts lines deleted
+    pub pedantry: StatCap,
+    /// Threshold counts tens of characters in the commit message
+    pub precision: StatCap,
+}
+
+impl Default for CapsConfig {
+    fn default() -> Self {
+        CapsConfig {
+            output: StatCap {
+                threshold: 500,
+                daily: 10000,
+                beyond_daily: 0.1,
+            },
+            pedantry: StatCap {
+                threshold: 1000,
+                daily: 10000,
+                beyond_daily: 0.1,
+            },
+            precision: StatCap {
+                threshold: 30,
+                daily: 2000,
+                beyond_daily: 0.1,
+            },
+        }
+    }
+}
+
+impl CapsConfig {
+    pub fn validate(&self) -> Result<(), (String, String)> {
+        for (key, cap) in [
+            ("caps.output.beyond_daily", &self.output),
+            ("caps.pedantry.beyond_daily", &self.pedantry),
+            ("caps.precision.beyond_daily", &self.precision),
+        ] {
+            if !(cap.beyond_daily.is_finite() && (0.0..=1.0).contains(&cap.beyond_daily)) {
+                return Err((key.to_string(), "must be between 0 and 1".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    fn get(&self, stat: XpType) -> Option<&StatCap> {
+        match stat {
+            XpType::Output => Some(&self.output),
+            XpType::Pedantry => Some(&self.pedantry),
+            XpType::Precision => Some(&self.precision),
+            XpType::Total | XpType::Knowledge => None,
+        }
+    }
+}
+
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CapKind {
+    /// An oversized commit counted logarithmically past the threshold
+    Diminished,
+    /// The stat had already earned its daily XP
+    Daily,
+}
+
+/// A cap that reduced the XP of a stat
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Cap {
+    pub stat: XpType,
+    pub kind: CapKind,
+}
+
+/// Count an amount past the threshold logarithmically, so huge commits earn little more than large ones
+pub fn diminish(stat: XpType, amount: f64) -> (f64, bool) {
+    let threshold = config().caps.get(stat).map_or(0, |c| c.threshold) as f64;
+    if threshold == 0.0 || amount <= threshold {
+        return (amount, false);
+    }
+    (threshold * (1.0 + (amount / threshold).ln()), true)
+}
+
+/// Reduce the XP beyond what is left of the stat's daily cap
+pub fn soft_cap(stat: XpType, xp: f64, earned_today: f64) -> (f64, bool) {
+    let Some(cap) = config().caps.get(stat).filter(|c| c.daily > 0) else {
+        return (xp, false);
+    };
+    let remaining = (cap.daily as f64 - earned_today).max(0.0);
+    if xp <= remaining {
+        return (xp, false);
+    }
+    (remaining + (xp - remaining) * cap.beyond_daily, true)
+}
+
+/// XP each stat earned from commits made on a day
+pub fn earned_on(history: &[CommitRecord], day: NaiveDate) -> XpBreakdown {
+    history
+        .iter()
+        .filter(|r| r.local_date() == day)
+        .fold(XpBreakdown::default(), |sum, r| XpBreakdown {
+            output: sum.output + r.breakdown.output,
+            pedantry: sum.pedantry + r.breakdown.pedantry,
+            precision: sum.precision + r.breakdown.precision,
+        })
+}
+
+pub fn announce(caps: &[Cap]) {
+    for cap in caps {
+        match cap.kind {
+            CapKind::Diminished => println!(
+                "📉 Diminishing returns: large commits earn less {:?} XP",
+                cap.stat
+            ),
+            CapKind::Daily => println!(
+                "📉 Daily cap reached: {:?} XP is reduced for the rest of the day",
+                cap.stat
+            ),
+        }
+    }
+}
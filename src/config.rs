@@ -0,0 +1,437 @@
+use crate::caps::CapsConfig;
+use crate::levelup::Celebration;
+use crate::policy::Policy;
+use crate::prestige::PrestigeConfig;
+use crate::progress::BarStyle;
+use crate::prompt::PromptConfig;
+use crate::quests::QuestConfig;
+use crate::respec::RespecConfig;
+use crate::scaling::{Curves, XpType};
+use crate::streak::StreakConfig;
+use crate::theme::ThemeConfig;
+use anyhow::{Context, Result, anyhow};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use toml::{Table, Value};
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Every tunable setting, layered from defaults, the config file, environment
+/// variables and `--config` flags in that order
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Where state is stored, the platform data directory when empty
+    pub data_dir: String,
+    pub scaling: Scaling,
+    pub progress: Progress,
+    pub animation: Animation,
+    pub theme: ThemeConfig,
+    /// XP required for each level of each stat
+    pub curves: Curves,
+    pub prestige: PrestigeConfig,
+    pub streak: StreakConfig,
+    pub quests: QuestConfig,
+    pub respec: RespecConfig,
+    /// Status line printed by `git ascend prompt`
+    pub prompt: PromptConfig,
+    /// Diminishing returns for large commits and daily soft caps per stat
+    pub caps: CapsConfig,
+    /// Scoring rules applied to every repository before its own policy file
+    pub policy: Policy,
+}
+
+/// Levels needed for a stat to add 1x to its multiplier
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Scaling {
+    pub output: f64,
+    pub precision: f64,
+    pub pedantry: f64,
+    pub knowledge: f64,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Scaling {
+            output: 10.0,
+            precision: 50.0,
+            pedantry: 5.0,
+            knowledge: 500.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Progress {
+    /// Width of the Total level bar
+    pub width: u32,
+    /// Width of the sub stat bars
+    pub short_width: u32,
+    pub style: BarStyle,
+    /// Shrink bars that would not fit in the terminal
+    pub fit_terminal: bool,
+    /// Used by the blocks style
+    pub filled_char: String,
+    pub empty_char: String,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            width: 50,
+            short_width: 25,
+            style: BarStyle::Blocks,
+            fit_terminal: true,
+            filled_char: String::from("█"),
+            empty_char: String::from("░"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Animation {
+    /// Maximum number of frames drawn when the progress bar animates
+    pub frames: u32,
+    pub frame_delay_ms: u64,
+    /// How a level up is shown while the bar animates
+    pub celebration: Celebration,
+}
+
+impl Default for Animation {
+    fn default() -> Self {
+        Animation {
+            frames: 50,
+            frame_delay_ms: 10,
+            celebration: Celebration::Full,
+        }
+    }
+}
+
+impl Config {
+    fn validate(&self) -> Result<(), (String, String)> {
+        let invalid = |key: &str, reason: &str| Err((key.to_string(), reason.to_string()));
+        for (key, value) in [
+            ("scaling.output", self.scaling.output),
+            ("scaling.precision", self.scaling.precision),
+            ("scaling.pedantry", self.scaling.pedantry),
+            ("scaling.knowledge", self.scaling.knowledge),
+        ] {
+            if !(value.is_finite() && value > 0.0) {
+                return invalid(key, "must be greater than 0");
+            }
+        }
+        self.policy.weights.validate("policy.")?;
+        for (key, value) in [
+            ("progress.width", self.progress.width),
+            ("progress.short_width", self.progress.short_width),
+            ("animation.frames", self.animation.frames),
+        ] {
+            if value == 0 {
+                return invalid(key, "must be at least 1");
+            }
+        }
+        for (key, xp_type) in [
+            ("curves.total", XpType::Total),
+            ("curves.precision", XpType::Precision),
+            ("curves.output", XpType::Output),
+            ("curves.pedantry", XpType::Pedantry),
+            ("curves.knowledge", XpType::Knowledge),
+        ] {
+            self.curves
+                .get(xp_type)
+                .validate()
+                .map_err(|reason| (key.to_string(), reason))?;
+        }
+        self.prestige.validate()?;
+        self.streak.validate()?;
+        self.respec.validate()?;
+        self.caps.validate()?;
+        for (key, value) in [
+            ("progress.filled_char", &self.progress.filled_char),
+            ("progress.empty_char", &self.progress.empty_char),
+        ] {
+            if value.chars().count() != 1 {
+                return invalid(key, "must be a single character");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The active configuration
+pub fn config() -> &'static Config {
+    CONFIG.get_or_init(|| load(&[]).unwrap_or_default())
+}
+
+/// Load the configuration once at startup, applying `key=value` overrides from the command line
+pub fn init(overrides: &[String]) -> Result<()> {
+    let config = load(overrides)?;
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+pub fn config_path() -> PathBuf {
+    ProjectDirs::from("io", "m51", "git-ascend")
+        .expect("Could not determine $HOME location")
+        .config_dir()
+        .join("config.toml")
+}
+
+fn load(overrides: &[String]) -> Result<Config> {
+    let table = layers(overrides, true)?;
+    deserialize(&table).map_err(|(key, e)| anyhow!("Invalid value for `{key}`: {e}"))
+}
+
+/// Make sure the config file and environment variables form a valid config
+pub fn verify() -> Result<()> {
+    load(&[]).map(|_| ())
+}
+
+/// Defaults with the config file, environment variables and `--config` flags layered on top,
+/// checking each layer when `strict` so errors name where the bad value came from
+fn layers(overrides: &[String], strict: bool) -> Result<Table> {
+    let check = |table: &Table, source: &str| {
+        if strict { check(table, source) } else { Ok(()) }
+    };
+    let mut table = defaults();
+    let keys: Vec<String> = flatten(&table).into_iter().map(|(k, _)| k).collect();
+
+    let path = config_path();
+    if path.exists() {
+        merge(&mut table, read_config_file()?);
+        check(&table, &path.display().to_string())?;
+    }
+
+    for key in &keys {
+        let var = env_var(key);
+        if let Ok(raw) = std::env::var(&var) {
+            set_key(&mut table, key, parse_value(&raw))?;
+            check(&table, &format!("environment variable {var}"))?;
+        }
+    }
+
+    for arg in overrides {
+        let (key, raw) = arg
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Invalid --config {arg}, expected key=value"))?;
+        set_key(&mut table, key.trim(), parse_value(raw.trim()))?;
+        check(&table, "--config")?;
+    }
+    Ok(table)
+}
+
+fn defaults() -> Table {
+    Table::try_from(Config::default()).expect("Default config is serializable")
+}
+
+fn read_config_file() -> Result<Table> {
+    let path = config_path();
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Could not read {}", path.display()))?;
+    toml::from_str(&content).with_context(|| format!("Invalid config file {}", path.display()))
+}
+
+fn deserialize(table: &Table) -> Result<Config, (String, String)> {
+    let config: Config = serde_path_to_error::deserialize(Value::Table(table.clone()))
+        .map_err(|e| (e.path().to_string(), e.inner().message().to_string()))?;
+    config.validate()?;
+    Ok(config)
+}
+
+/// Make sure the merged layers still form a valid config, naming the culprit if not
+fn check(table: &Table, source: &str) -> Result<()> {
+    deserialize(table)
+        .map(|_| ())
+        .map_err(|(key, e)| anyhow!("Invalid value for `{key}` in {source}: {e}"))
+}
+
+/// Environment variable overriding a key, `progress.width` is `GIT_ASCEND_PROGRESS_WIDTH`
+fn env_var(key: &str) -> String {
+    format!("GIT_ASCEND_{}", key.to_uppercase().replace('.', "_"))
+}
+
+/// Interpret a command line or environment value as TOML, falling back to a plain string
+fn parse_value(raw: &str) -> Value {
+    toml::from_str::<Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut t| t.remove("value"))
+        .unwrap_or_else(|| Value::String(raw.to_string()))
+}
+
+/// Tag of tables such as curves whose other fields depend on it
+const KIND: &str = "kind";
+
+fn merge(base: &mut Table, layer: Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(Value::Table(base_table)), Value::Table(layer_table))
+                if layer_table
+                    .get(KIND)
+                    .is_none_or(|kind| base_table.get(KIND) == Some(kind)) =>
+            {
+                merge(base_table, layer_table)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn set_key(table: &mut Table, key: &str, value: Value) -> Result<()> {
+    let mut parts: Vec<&str> = key.split('.').collect();
+    let last = parts.pop().filter(|k| !k.is_empty());
+    let last = last.ok_or_else(|| anyhow!("Invalid config key `{key}`"))?;
+    let mut current = table;
+    for part in parts {
+        let entry = current
+            .entry(part)
+            .or_insert_with(|| Value::Table(Table::new()));
+        current = entry
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("Invalid config key `{key}`, `{part}` is not a section"))?;
+    }
+    if last == KIND && current.get(KIND) != Some(&value) {
+        current.clear();
+    }
+    current.insert(last.to_string(), value);
+    Ok(())
+}
+
+fn flatten(table: &Table) -> Vec<(String, Value)> {
+    let mut entries = Vec::new();
+    for (key, value) in table {
+        match value {
+            Value::Table(inner) => entries.extend(
+                flatten(inner)
+                    .into_iter()
+                    .map(|(k, v)| (format!("{key}.{k}"), v)),
+            ),
+            value => entries.push((key.clone(), value.clone())),
+        }
+    }
+    entries
+}
+
+/// Every layered setting, read without checking so a broken config can still be inspected
+fn effective(overrides: &[String]) -> Result<Vec<(String, Value)>> {
+    Ok(flatten(&layers(overrides, false)?))
+}
+
+/// Print the effective value of a key
+pub fn get(key: &str, overrides: &[String]) -> Result<()> {
+    let (_, value) = effective(overrides)?
+        .into_iter()
+        .find(|(k, _)| k == key)
+        .ok_or_else(|| anyhow!("Unknown config key `{key}`"))?;
+    match value {
+        Value::String(s) => println!("{s}"),
+        value => println!("{value}"),
+    }
+    Ok(())
+}
+
+/// Store a key in the config file after making sure the result is valid
+pub fn set(key: &str, raw: &str) -> Result<()> {
+    let mut file_table = read_file_layer()?;
+    let before = file_table.clone();
+    set_key(&mut file_table, key, parse_value(raw))?;
+    write_file_layer(&before, &file_table)?;
+    println!("Set {key} in {}", config_path().display());
+    Ok(())
+}
+
+/// Remove a key from the config file so it falls back to its default
+pub fn unset(key: &str) -> Result<()> {
+    let mut file_table = read_file_layer()?;
+    let before = file_table.clone();
+    if !remove_key(&mut file_table, key) {
+        return Err(anyhow!("`{key}` is not set in {}", config_path().display()));
+    }
+    write_file_layer(&before, &file_table)?;
+    println!("Unset {key} in {}", config_path().display());
+    Ok(())
+}
+
+fn read_file_layer() -> Result<Table> {
+    if config_path().exists() {
+        read_config_file()
+    } else {
+        Ok(Table::new())
+    }
+}
+
+/// Write the config file if the change leaves it valid, or at least adds no new problem
+/// so a broken file can be repaired one key at a time
+fn write_file_layer(before: &Table, after: &Table) -> Result<()> {
+    let path = config_path();
+    let known = problems(before);
+    let problems = problems(after);
+    if let Some((key, e)) = problems
+        .iter()
+        .find(|(key, _)| !known.iter().any(|(k, _)| k == key))
+    {
+        return Err(anyhow!(
+            "Invalid value for `{key}` in {}: {e}",
+            path.display()
+        ));
+    }
+    for (key, e) in &problems {
+        println!("`{key}` is still invalid: {e}");
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, toml::to_string_pretty(after)?)
+        .with_context(|| format!("Could not write {}", path.display()))
+}
+
+/// Every key making a config file layer invalid, found by dropping each culprit in turn
+fn problems(layer: &Table) -> Vec<(String, String)> {
+    let mut layer = layer.clone();
+    let mut problems = Vec::new();
+    loop {
+        let mut table = defaults();
+        merge(&mut table, layer.clone());
+        let Err((key, e)) = deserialize(&table) else {
+            return problems;
+        };
+        let removed = remove_key(&mut layer, &key);
+        problems.push((key, e));
+        if !removed {
+            return problems;
+        }
+    }
+}
+
+/// Remove a dotted key, dropping sections it leaves empty, and report whether it was there
+fn remove_key(table: &mut Table, key: &str) -> bool {
+    match key.split_once('.') {
+        None => table.remove(key).is_some(),
+        Some((section, rest)) => {
+            let Some(Value::Table(inner)) = table.get_mut(section) else {
+                return false;
+            };
+            let removed = remove_key(inner, rest);
+            if inner.is_empty() {
+                table.remove(section);
+            }
+            removed
+        }
+    }
+}
+
+/// Print every effective setting
+pub fn list(overrides: &[String]) -> Result<()> {
+    for (key, value) in effective(overrides)? {
+        println!("{key} = {value}");
+    }
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use crate::output::{OutputFormat, SCHEMA_VERSION, emit};
+use crate::progress::{bar_width, render_bar};
+use crate::scaling::{SUB_STATS, XpType, get_level_progression, multiplier};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How the curve is printed when no machine readable format is asked for
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CurveStyle {
+    Table,
+    Csv,
+    Chart,
+}
+
+#[derive(Serialize)]
+struct CurveReport {
+    version: u32,
+    stat: XpType,
+    levels: Vec<CurveLevel>,
+}
+
+#[derive(Serialize)]
+struct CurveLevel {
+    level: u32,
+    /// XP this level takes
+    xp: u128,
+    /// XP needed to reach this level from 0
+    total_xp: u128,
+    /// Multiplier each sub stat gives at this level
+    output: f64,
+    pedantry: f64,
+    precision: f64,
+    knowledge: f64,
+}
+
+/// Print how much XP each level of a stat takes
+pub fn curve(
+    xp_type: XpType,
+    max_level: u32,
+    step: u32,
+    style: CurveStyle,
+    format: OutputFormat,
+) -> Result<()> {
+    let progression: Vec<_> = get_level_progression(max_level, xp_type)
+        .into_iter()
+        .filter(|(level, _, _)| level % step.max(1) == 0 || *level == 1)
+        .collect();
+    emit(
+        format,
+        || {
+            Ok(CurveReport {
+                version: SCHEMA_VERSION,
+                stat: xp_type,
+                levels: progression
+                    .iter()
+                    .map(|&(level, xp, total_xp)| CurveLevel {
+                        level,
+                        xp,
+                        total_xp,
+                        output: multiplier(level, XpType::Output),
+                        pedantry: multiplier(level, XpType::Pedantry),
+                        precision: multiplier(level, XpType::Precision),
+                        knowledge: multiplier(level, XpType::Knowledge),
+                    })
+                    .collect(),
+            })
+        },
+        || {
+            match style {
+                CurveStyle::Table => print_table(&progression),
+                CurveStyle::Csv => print_csv(&progression),
+                CurveStyle::Chart => print_chart(&progression),
+            }
+            Ok(())
+        },
+    )
+}
+
+fn print_table(progression: &[(u32, u128, u128)]) {
+    print!("{:>6} {:>12} {:>16}", "Level", "XP", "Total XP");
+    for stat in SUB_STATS {
+        print!(" {:>10}", format!("{stat:?}"));
+    }
+    println!();
+    for (level, xp, total) in progression {
+        print!("{level:>6} {xp:>12} {total:>16}");
+        for stat in SUB_STATS {
+            print!(" {:>9.2}x", multiplier(*level, stat));
+        }
+        println!();
+    }
+}
+
+fn print_csv(progression: &[(u32, u128, u128)]) {
+    println!("level,xp,total_xp,output,pedantry,precision,knowledge");
+    for (level, xp, total) in progression {
+        let multipliers: Vec<String> = SUB_STATS
+            .iter()
+            .map(|stat| multiplier(*level, *stat).to_string())
+            .collect();
+        println!("{level},{xp},{total},{}", multipliers.join(","));
+    }
+}
+
+/// Horizontal bars of the total XP needed for each level
+fn print_chart(progression: &[(u32, u128, u128)]) {
+    let width = bar_width(None) as usize;
+    let max_total = progression.last().map(|(_, _, total)| *total).unwrap_or(0);
+    for (level, _, total) in progression {
+        let fraction = if max_total == 0 {
+            0.0
+        } else {
+            *total as f64 / max_total as f64
+        };
+        let (filled, empty) = render_bar(fraction, width);
+        println!("{level:>6} {filled}{empty} {total}");
+    }
+}
@@ -0,0 +1,273 @@
+use crate::allocation::Allocation;
+use crate::ascii::display_number_x;
+use crate::history::{repo_name, repo_totals};
+use crate::output::LevelReport;
+use crate::scaling::{SUB_STATS, XpType};
+use crate::state::{CommitRecord, RepoState, lock_state, read_history, repos, set_allocation};
+use crate::stats::{StatsReport, stats_report};
+use crate::theme::{bar_color, color_enabled};
+use anyhow::Result;
+use chrono::{Duration as Days, Local, NaiveDate};
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color as TuiColor, Modifier, Style, Stylize};
+use ratatui::symbols::Marker;
+use ratatui::text::{Line, Text};
+use ratatui::widgets::{Axis, Block, Chart, Dataset, Gauge, GraphType, Paragraph, Row, Table};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Days shown in the XP chart
+const CHART_DAYS: i64 = 90;
+/// How often state is reloaded so XP collected by hooks shows up
+const REFRESH: Duration = Duration::from_secs(2);
+
+/// Stats in the order `git ascend switch` lists them, with the key that selects each
+const SWITCH_KEYS: [(char, XpType); 4] = [
+    ('1', XpType::Precision),
+    ('2', XpType::Output),
+    ('3', XpType::Pedantry),
+    ('4', XpType::Knowledge),
+];
+
+struct Data {
+    stats: StatsReport,
+    history: Vec<CommitRecord>,
+    repos: HashMap<String, RepoState>,
+}
+
+impl Data {
+    fn load() -> Result<Self> {
+        Ok(Data {
+            stats: stats_report()?,
+            history: read_history()?,
+            repos: repos()?,
+        })
+    }
+}
+
+/// Full screen view of levels, recent XP and repositories
+pub fn dashboard() -> Result<()> {
+    let mut data = Data::load()?;
+    let mut terminal = ratatui::init();
+    let result = run(&mut terminal, &mut data);
+    ratatui::restore();
+    result
+}
+
+fn run(terminal: &mut DefaultTerminal, data: &mut Data) -> Result<()> {
+    loop {
+        terminal.draw(|frame| draw(frame, data))?;
+        if !event::poll(REFRESH)? {
+            *data = Data::load()?;
+            continue;
+        }
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char(c) => {
+                if let Some((_, stat)) = SWITCH_KEYS.iter().find(|(k, _)| *k == c) {
+                    let lock = lock_state()?;
+                    set_allocation(&lock, Allocation::single(*stat))?;
+                }
+                *data = Data::load()?;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn draw(frame: &mut Frame, data: &Data) {
+    let [header, chart, tables, footer] = Layout::vertical([
+        Constraint::Length(10),
+        Constraint::Min(8),
+        Constraint::Min(8),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [level, stats] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(header);
+    let [commits, repos] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(tables);
+
+    draw_level(frame, level, &data.stats);
+    draw_stats(frame, stats, &data.stats);
+    draw_chart(frame, chart, &data.history);
+    draw_commits(frame, commits, data);
+    draw_repos(frame, repos, data);
+
+    let keys: Vec<String> = SWITCH_KEYS
+        .iter()
+        .map(|(key, stat)| format!("{key} {stat:?}"))
+        .collect();
+    frame.render_widget(
+        Line::from(format!(" {}  q quit", keys.join("  "))).dim(),
+        footer,
+    );
+}
+
+fn draw_level(frame: &mut Frame, area: Rect, stats: &StatsReport) {
+    let block = Block::bordered().title(" Total ");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let [number, bar] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(inner);
+    let digits = display_number_x(stats.total.level, stats.prestige.count);
+    frame.render_widget(
+        Paragraph::new(Text::from(digits.trim_matches('\n'))),
+        number,
+    );
+    frame.render_widget(gauge(&stats.total, XpType::Total, String::new()), bar);
+}
+
+fn draw_stats(frame: &mut Frame, area: Rect, stats: &StatsReport) {
+    let block = Block::bordered().title(format!(
+        " Streak {} days ({:.2}x) ",
+        stats.streak.current, stats.streak.multiplier
+    ));
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let rows = Layout::vertical([Constraint::Length(2); 4]).split(inner);
+    for (row, stat) in rows.iter().zip(SUB_STATS) {
+        let level = stats.level(stat);
+        let share = stats
+            .active
+            .iter()
+            .find(|s| s.stat == stat)
+            .map(|s| format!(" ◆ {}%", s.percent))
+            .unwrap_or_default();
+        let label = format!(
+            "{stat:?} {}  {:.2}x{share}",
+            level.level,
+            level.multiplier.unwrap_or(1.0)
+        );
+        let mut gauge = gauge(level, stat, label);
+        if !share.is_empty() {
+            gauge = gauge.add_modifier(Modifier::BOLD);
+        }
+        frame.render_widget(gauge, Rect { height: 1, ..*row });
+    }
+}
+
+fn gauge(level: &LevelReport, stat: XpType, label: String) -> Gauge<'static> {
+    let ratio = if level.needed == 0 {
+        0.0
+    } else {
+        (level.progress as f64 / level.needed as f64).clamp(0.0, 1.0)
+    };
+    Gauge::default()
+        .ratio(ratio)
+        .label(label)
+        .gauge_style(Style::new().fg(tui_color(stat)))
+        .use_unicode(true)
+}
+
+fn tui_color(stat: XpType) -> TuiColor {
+    match bar_color(stat).to_ratatui() {
+        Some(color) if color_enabled() => color,
+        _ => TuiColor::Reset,
+    }
+}
+
+/// Total XP earned from commits over the last days, by commit date
+fn draw_chart(frame: &mut Frame, area: Rect, history: &[CommitRecord]) {
+    let today = Local::now().date_naive();
+    let first = today - Days::days(CHART_DAYS - 1);
+    let mut daily = vec![0u128; CHART_DAYS as usize];
+    let mut before = 0u128;
+    for record in history {
+        let day = record.local_date();
+        if day < first {
+            before += record.xp;
+        } else if day <= today {
+            daily[(day - first).num_days() as usize] += record.xp;
+        }
+    }
+    let points: Vec<(f64, f64)> = daily
+        .iter()
+        .scan(before, |total, xp| {
+            *total += xp;
+            Some(*total as f64)
+        })
+        .enumerate()
+        .map(|(i, total)| (i as f64, total))
+        .collect();
+    let low = points.first().map_or(0.0, |p| p.1);
+    let high = points.last().map_or(0.0, |p| p.1).max(low + 1.0);
+
+    let dataset = Dataset::default()
+        .marker(Marker::Braille)
+        .graph_type(GraphType::Line)
+        .fg(tui_color(XpType::Total))
+        .data(&points);
+    let date = |day: NaiveDate| day.format("%b %d").to_string();
+    let chart = Chart::new(vec![dataset])
+        .block(Block::bordered().title(format!(" XP over the last {CHART_DAYS} days ")))
+        .x_axis(
+            Axis::default()
+                .bounds([0.0, (CHART_DAYS - 1) as f64])
+                .labels([date(first), date(today)]),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([low, high])
+                .labels([format!("{low:.0}"), format!("{high:.0}")]),
+        );
+    frame.render_widget(chart, area);
+}
+
+fn draw_commits(frame: &mut Frame, area: Rect, data: &Data) {
+    let rows = data
+        .history
+        .iter()
+        .rev()
+        .take(area.height as usize)
+        .map(|r| {
+            Row::new([
+                r.local_date().format("%Y-%m-%d").to_string(),
+                r.sha.chars().take(7).collect(),
+                repo_name(&data.repos, &r.repo_id),
+                format!("+{} XP", r.xp),
+            ])
+        });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(10),
+            Constraint::Length(7),
+            Constraint::Fill(1),
+            Constraint::Length(12),
+        ],
+    )
+    .header(Row::new(["Date", "Commit", "Repository", "XP"]).bold())
+    .block(Block::bordered().title(" Recent commits "));
+    frame.render_widget(table, area);
+}
+
+fn draw_repos(frame: &mut Frame, area: Rect, data: &Data) {
+    let rows = repo_totals(&data.history)
+        .into_iter()
+        .map(|(repo_id, totals)| {
+            Row::new([
+                repo_name(&data.repos, repo_id),
+                totals.commits.to_string(),
+                format!("{} XP", totals.xp),
+            ])
+        });
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Fill(1),
+            Constraint::Length(7),
+            Constraint::Length(12),
+        ],
+    )
+    .header(Row::new(["Repository", "Commits", "XP"]).bold())
+    .block(Block::bordered().title(" Repositories "));
+    frame.render_widget(table, area);
+}
@@ -0,0 +1,196 @@
+use crate::config;
+use crate::git::{GitRepo, git_username};
+use crate::setup::{data_location, first_run};
+use crate::state::{check_state, repo_state, state_path};
+use crate::theme::{Color, paint};
+use anyhow::Result;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+struct Check {
+    name: &'static str,
+    problem: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str) -> Self {
+        Check {
+            name,
+            problem: None,
+        }
+    }
+
+    fn fail(name: &'static str, problem: String) -> Self {
+        Check {
+            name,
+            problem: Some(problem),
+        }
+    }
+}
+
+/// Diagnose why commits in the current repository might not be awarding XP
+pub fn doctor(repo_path: &str) -> Result<()> {
+    let mut checks = vec![check_config(), check_data_dir()];
+    if !first_run() {
+        checks.push(check_state_file());
+    }
+    // Registration can only be checked against a readable state file
+    let state_ok = checks.iter().all(|c| c.problem.is_none());
+    checks.push(check_username());
+    match GitRepo::new(repo_path) {
+        Ok(repo) => checks.extend(check_repo(&repo, repo_path, state_ok)),
+        Err(e) => checks.push(Check::fail(
+            "Git repository",
+            format!("{e}. Run `git ascend doctor` from inside a git repository."),
+        )),
+    }
+
+    let mut failures = 0;
+    for check in &checks {
+        match &check.problem {
+            None => println!("{} {}", paint("✔", Color::Green), check.name),
+            Some(problem) => {
+                failures += 1;
+                println!("{} {}", paint("✘", Color::Red), check.name);
+                println!("    {problem}");
+            }
+        }
+    }
+    println!();
+    if failures == 0 {
+        println!("Everything looks good. Your ascension continues.");
+    } else {
+        println!("Found {failures} problem(s).");
+    }
+    Ok(())
+}
+
+fn check_config() -> Check {
+    let name = "Config";
+    match config::verify() {
+        Ok(()) => Check::ok(name),
+        Err(e) => Check::fail(
+            name,
+            format!(
+                "{e:#}. Other commands refuse to run until it is fixed with `git ascend config set` or `git ascend config unset`."
+            ),
+        ),
+    }
+}
+
+fn check_data_dir() -> Check {
+    let name = "Data directory";
+    if first_run() {
+        Check::fail(
+            name,
+            format!(
+                "{} does not exist. Run `git ascend setup` to create it.",
+                data_location()
+            ),
+        )
+    } else {
+        Check::ok(name)
+    }
+}
+
+fn check_state_file() -> Check {
+    let name = "State file";
+    if !state_path().exists() {
+        return Check::ok(name);
+    }
+    match check_state() {
+        Ok(()) => Check::ok(name),
+        Err(e) => Check::fail(
+            name,
+            format!(
+                "{e:#}. Move {} out of the way to start over.",
+                state_path().display()
+            ),
+        ),
+    }
+}
+
+fn check_username() -> Check {
+    let name = "Git user.name";
+    match git_username() {
+        Ok(_) => Check::ok(name),
+        Err(_) => Check::fail(
+            name,
+            "user.name is not set. Run `git config --global user.name \"Your Name\"`.".to_string(),
+        ),
+    }
+}
+
+fn check_repo(repo: &GitRepo, repo_path: &str, state_ok: bool) -> Vec<Check> {
+    let mut checks = vec![Check::ok("Git repository")];
+
+    match repo.hooks_path_override() {
+        Some(hooks_path) => checks.push(Check::fail(
+            "Hooks path",
+            format!(
+                "core.hooksPath is set to {hooks_path}, so .git/hooks is ignored. Add `git ascend` to {hooks_path}/post-commit."
+            ),
+        )),
+        None => checks.push(Check::ok("Hooks path")),
+    }
+    checks.push(check_hook(repo_path));
+    if !state_ok {
+        return checks;
+    }
+
+    let repo_id = match repo.id() {
+        Ok(id) => id,
+        Err(e) => {
+            checks.push(Check::fail("Repository registered", e.to_string()));
+            return checks;
+        }
+    };
+    match repo_state(&repo_id) {
+        Ok(state) => {
+            checks.push(Check::ok("Repository registered"));
+            if repo.has_commit(&state.last_recorded_commit) {
+                checks.push(Check::ok("Recorded commit"));
+            } else {
+                checks.push(Check::fail(
+                    "Recorded commit",
+                    format!(
+                        "Last recorded commit {} is missing, probably after a rebase. Run `git ascend setup` to record from HEAD.",
+                        state.last_recorded_commit
+                    ),
+                ));
+            }
+        }
+        Err(_) => checks.push(Check::fail(
+            "Repository registered",
+            "This repository is not registered. Run `git ascend setup`.".to_string(),
+        )),
+    }
+    checks
+}
+
+fn check_hook(repo_path: &str) -> Check {
+    let name = "Post-commit hook";
+    let post_commit = format!("{repo_path}/.git/hooks/post-commit");
+    let post_commit_path = Path::new(&post_commit);
+    let Ok(metadata) = fs::metadata(post_commit_path) else {
+        return Check::fail(
+            name,
+            format!("{post_commit} does not exist. Run `git ascend setup`."),
+        );
+    };
+    if metadata.permissions().mode() & 0o111 == 0 {
+        return Check::fail(
+            name,
+            format!("{post_commit} is not executable. Run `chmod +x {post_commit}`."),
+        );
+    }
+    let content = fs::read_to_string(post_commit_path).unwrap_or_default();
+    if !content.contains("git ascend") {
+        return Check::fail(
+            name,
+            format!("{post_commit} does not call `git ascend`. Add it to the hook."),
+        );
+    }
+    Check::ok(name)
+}
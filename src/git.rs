@@ -1,5 +1,6 @@
 use anyhow::{Context, Result, anyhow};
-use git2::{Commit, Config, Repository};
+use git2::{Commit, Config, Patch, Repository};
+use std::path::Path;
 
 pub struct GitRepo {
     repo: Repository,
@@ -11,6 +12,16 @@ impl GitRepo {
         Ok(GitRepo { repo })
     }
 
+    /// Absolute path of the repository's working directory
+    pub fn workdir(&self) -> Result<String> {
+        let workdir = self
+            .repo
+            .workdir()
+            .context("Bare repositories are not supported")?;
+        let workdir = std::fs::canonicalize(workdir).unwrap_or_else(|_| workdir.to_path_buf());
+        Ok(workdir.to_string_lossy().into_owned())
+    }
+
     pub fn id(&self) -> Result<String> {
         self.first_commit_hash()
     }
@@ -48,29 +59,38 @@ impl GitRepo {
             .push_range(&format!("{commit}..HEAD"))
             .context("Specified commit not found in this repository")?;
 
-        revwalk
-            .map(|oid| {
-                let oid = oid?;
-                let commit = self.repo.find_commit(oid)?;
-                let (lines_added, lines_deleted) =
-                    calculate_commit_diff_stats(&self.repo, &commit)?;
-
-                Ok(CommitStats {
-                    sha: commit.id().to_string(),
-                    message: commit.message().unwrap_or("").to_string(),
-                    author: commit.author().name().unwrap_or("Unknown").to_string(),
-                    lines_added,
-                    lines_deleted,
-                })
-            })
-            .filter(|result| {
-                if let Ok(stats) = result {
-                    stats.author == author
-                } else {
-                    false
-                }
-            })
-            .collect()
+        Ok(revwalk
+            .filter_map(|oid| self.repo.find_commit(oid.ok()?).ok())
+            // Other authors' commits earn nothing, so they are never diffed or test-merged
+            .filter(|commit| commit.author().name().unwrap_or("Unknown") == author)
+            .filter_map(|commit| commit_stats(&self.repo, &commit).ok())
+            .collect())
+    }
+
+    /// Contents of a file in the tree at HEAD, if it exists
+    pub fn file_at_head(&self, path: &str) -> Result<Option<String>> {
+        let tree = self.repo.head()?.peel_to_tree()?;
+        let Ok(entry) = tree.get_path(Path::new(path)) else {
+            return Ok(None);
+        };
+        let blob = entry.to_object(&self.repo)?.peel_to_blob()?;
+        let content = std::str::from_utf8(blob.content())
+            .with_context(|| format!("{path} is not valid UTF-8"))?;
+        Ok(Some(content.to_string()))
+    }
+
+    pub fn has_commit(&self, sha: &str) -> bool {
+        git2::Oid::from_str(sha)
+            .and_then(|oid| self.repo.find_commit(oid))
+            .is_ok()
+    }
+
+    /// Value of `core.hooksPath` if the repository overrides where hooks live
+    pub fn hooks_path_override(&self) -> Option<String> {
+        self.repo
+            .config()
+            .and_then(|config| config.get_string("core.hooksPath"))
+            .ok()
     }
 
     pub fn head_commit_hash(&self) -> Result<String> {
@@ -84,36 +104,83 @@ impl GitRepo {
 pub struct CommitStats {
     pub sha: String,
     pub message: String,
-    pub author: String,
+    /// Commit time in seconds since the epoch
+    pub timestamp: i64,
+    /// Offset of the committer's time zone from UTC
+    pub offset_minutes: i32,
+    /// Whether this is a merge whose parents could not be merged automatically
+    pub resolved_conflicts: bool,
+    pub files: Vec<FileChange>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub path: String,
     pub lines_added: u32,
     pub lines_deleted: u32,
 }
 
-/// Returns insertions, deletions for a given commit
+fn commit_stats(repo: &Repository, commit: &Commit) -> Result<CommitStats, git2::Error> {
+    Ok(CommitStats {
+        sha: commit.id().to_string(),
+        message: commit.message().unwrap_or("").to_string(),
+        timestamp: commit.time().seconds(),
+        offset_minutes: commit.time().offset_minutes(),
+        resolved_conflicts: resolved_conflicts(repo, commit)?,
+        files: calculate_commit_diff_stats(repo, commit)?,
+    })
+}
+
+/// Returns the lines inserted and deleted in each file of a given commit
 fn calculate_commit_diff_stats(
     repo: &Repository,
     commit: &Commit,
-) -> Result<(u32, u32), git2::Error> {
+) -> Result<Vec<FileChange>, git2::Error> {
     let commit_tree = commit.tree()?;
     let parent_count = commit.parent_count();
 
-    if parent_count == 0 {
+    let diff = if parent_count == 0 {
         // Initial commit - diff against empty tree
-        let diff = repo.diff_tree_to_tree(None, Some(&commit_tree), None)?;
-        let stats = diff.stats()?;
-        Ok((stats.insertions() as u32, stats.deletions() as u32))
+        repo.diff_tree_to_tree(None, Some(&commit_tree), None)?
     } else if parent_count == 1 {
         // Regular commit - use first parent
         let parent = commit.parent(0)?;
         let parent_tree = parent.tree()?;
-
-        let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit_tree), None)?;
-        let stats = diff.stats()?;
-        Ok((stats.insertions() as u32, stats.deletions() as u32))
+        repo.diff_tree_to_tree(Some(&parent_tree), Some(&commit_tree), None)?
     } else {
         // Merge Commit
-        Ok((0, 0))
+        return Ok(Vec::new());
+    };
+
+    let mut files = Vec::new();
+    for idx in 0..diff.deltas().len() {
+        let Some(patch) = Patch::from_diff(&diff, idx)? else {
+            continue;
+        };
+        let delta = patch.delta();
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let (_, lines_added, lines_deleted) = patch.line_stats()?;
+        files.push(FileChange {
+            path,
+            lines_added: lines_added as u32,
+            lines_deleted: lines_deleted as u32,
+        });
+    }
+    Ok(files)
+}
+
+/// Whether merging the parents of a merge commit produces conflicts that had to be resolved
+fn resolved_conflicts(repo: &Repository, commit: &Commit) -> Result<bool, git2::Error> {
+    if commit.parent_count() != 2 {
+        return Ok(false);
     }
+    let index = repo.merge_commits(&commit.parent(0)?, &commit.parent(1)?, None)?;
+    Ok(index.has_conflicts())
 }
 
 pub fn git_username() -> Result<String> {
@@ -0,0 +1,84 @@
+use crate::history::{daily_xp, repo_name};
+use crate::scaling::XpType;
+use crate::state::{read_history, read_quest_rewards, repos};
+use crate::theme::{bar_color, bold, dim, paint};
+use anyhow::{Result, anyhow};
+use chrono::{Datelike, Duration as Days, Local};
+
+/// Weeks shown, enough to always cover the last year
+const WEEKS: i64 = 53;
+/// Day without XP, then XP in quarters of the busiest day
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Calendar of the XP earned each day over the last year, by commit date
+pub fn heatmap(repo: Option<String>, stat: XpType) -> Result<()> {
+    let repos = repos()?;
+    let mut history = read_history()?;
+    let mut rewards = read_quest_rewards()?;
+    if let Some(repo) = &repo {
+        history.retain(|r| repo_name(&repos, &r.repo_id) == *repo || r.repo_id.starts_with(repo));
+        // Quests are not earned in any one repository
+        rewards.clear();
+        if history.is_empty() {
+            return Err(anyhow!("No XP was earned in a repository named {repo}"));
+        }
+    }
+
+    let today = Local::now().date_naive();
+    let first =
+        today - Days::days(today.weekday().num_days_from_monday() as i64) - Days::weeks(WEEKS - 1);
+    let daily = daily_xp(&history, &rewards, first, today, stat);
+    let max = daily.iter().copied().max().unwrap_or(0);
+    let shade = |xp: u128| {
+        if xp == 0 {
+            dim(&SHADES[0].to_string())
+        } else {
+            let idx = (xp * 4).div_ceil(max) as usize;
+            paint(&SHADES[idx].to_string(), bar_color(stat))
+        }
+    };
+
+    // Label the week each month's first Monday falls in, skipping labels that would overlap
+    let mut months = vec![' '; WEEKS as usize];
+    let mut free_from = 0;
+    for week in 0..months.len() {
+        let monday = first + Days::weeks(week as i64);
+        if monday.day() <= 7 && week >= free_from && week + 3 <= months.len() {
+            for (i, c) in monday.format("%b").to_string().chars().enumerate() {
+                months[week + i] = c;
+            }
+            free_from = week + 4;
+        }
+    }
+    println!("    {}", dim(months.iter().collect::<String>().trim_end()));
+    for weekday in 0..7 {
+        let name = ["Mon", "", "Wed", "", "Fri", "", "Sun"][weekday as usize];
+        let mut row = format!("{name:<4}");
+        for week in 0..WEEKS {
+            let day = first + Days::weeks(week) + Days::days(weekday);
+            if day > today {
+                break;
+            }
+            row.push_str(&shade(daily[(day - first).num_days() as usize]));
+        }
+        println!("{row}");
+    }
+
+    let total: u128 = daily.iter().sum();
+    let active = daily.iter().filter(|xp| **xp > 0).count();
+    let scope = match &repo {
+        Some(repo) => format!("{stat:?} XP in {repo}"),
+        None => format!("{stat:?} XP"),
+    };
+    println!();
+    println!(
+        "{} on {active} days in the last year    Less {} More",
+        bold(&format!("{total} {scope}")),
+        SHADES
+            .iter()
+            .map(char::to_string)
+            .collect::<Vec<_>>()
+            .join(" ")
+    );
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use crate::output::{OutputFormat, SCHEMA_VERSION, ShareReport, allocation_report, emit};
+use crate::quests;
+use crate::scaling::XpType;
+use crate::state::{
+    CommitRecord, QuestReward, RepoState, Respec, XpBreakdown, read_history, read_quest_rewards,
+    read_respecs, repos,
+};
+use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+enum Entry<'a> {
+    Commit(&'a CommitRecord),
+    Quest(&'a QuestReward),
+    Respec(&'a Respec),
+}
+
+impl Entry<'_> {
+    fn timestamp(&self) -> i64 {
+        match self {
+            Entry::Commit(record) => record.timestamp,
+            Entry::Quest(reward) => reward.timestamp,
+            Entry::Respec(respec) => respec.timestamp,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct HistoryReport<'a> {
+    version: u32,
+    /// Oldest first
+    entries: Vec<EntryReport<'a>>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum EntryReport<'a> {
+    Commit {
+        timestamp: i64,
+        sha: &'a str,
+        repo: String,
+        lines_added: u32,
+        lines_deleted: u32,
+        xp: u128,
+        breakdown: &'a XpBreakdown,
+        active: Vec<ShareReport>,
+    },
+    Quest {
+        timestamp: i64,
+        key: &'a str,
+        xp: u128,
+        active: Vec<ShareReport>,
+    },
+    Respec {
+        timestamp: i64,
+        from: XpType,
+        to: XpType,
+        amount: u128,
+        tax: u128,
+    },
+}
+
+/// Directory name of a repository, or the start of its id when its path is unknown
+pub fn repo_name(repos: &HashMap<String, RepoState>, repo_id: &str) -> String {
+    repos
+        .get(repo_id)
+        .and_then(|r| r.path.as_deref())
+        .and_then(|p| Path::new(p).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| repo_id.chars().take(7).collect())
+}
+
+#[derive(Default)]
+pub struct RepoTotals {
+    pub commits: u32,
+    pub lines_added: u64,
+    pub lines_deleted: u64,
+    pub xp: u128,
+}
+
+/// Commits, lines and XP of every repository in the history, most XP first
+pub fn repo_totals(history: &[CommitRecord]) -> Vec<(&str, RepoTotals)> {
+    let mut totals: HashMap<&str, RepoTotals> = HashMap::new();
+    for record in history {
+        let entry = totals.entry(&record.repo_id).or_default();
+        entry.commits += 1;
+        entry.lines_added += record.lines_added as u64;
+        entry.lines_deleted += record.lines_deleted as u64;
+        entry.xp += record.xp;
+    }
+    let mut totals: Vec<_> = totals.into_iter().collect();
+    totals.sort_by_key(|(_, totals)| std::cmp::Reverse(totals.xp));
+    totals
+}
+
+/// XP a stat received on each day from `first` to `last` from commits, by commit date, and quests
+pub fn daily_xp(
+    history: &[CommitRecord],
+    rewards: &[QuestReward],
+    first: NaiveDate,
+    last: NaiveDate,
+    stat: XpType,
+) -> Vec<u128> {
+    let mut daily = vec![0; (last - first).num_days() as usize + 1];
+    let earned = history
+        .iter()
+        .map(|r| (r.local_date(), r.allocation.xp_for(r.xp, stat)))
+        .chain(
+            rewards
+                .iter()
+                .map(|r| (r.local_date(), r.allocation.xp_for(r.xp, stat))),
+        );
+    for (day, xp) in earned {
+        if (first..=last).contains(&day) {
+            daily[(day - first).num_days() as usize] += xp;
+        }
+    }
+    daily
+}
+
+/// List the most recent commits and quests XP was awarded for and XP moved between stats
+pub fn history(limit: usize, format: OutputFormat) -> Result<()> {
+    let commits = read_history()?;
+    let rewards = read_quest_rewards()?;
+    let respecs = read_respecs()?;
+    let repos = repos()?;
+    let repo_name = |repo_id: &str| repo_name(&repos, repo_id);
+
+    let mut entries: Vec<Entry> = commits
+        .iter()
+        .map(Entry::Commit)
+        .chain(rewards.iter().map(Entry::Quest))
+        .chain(respecs.iter().map(Entry::Respec))
+        .collect();
+    // Stable so commits made in the same second keep their order
+    entries.sort_by_key(|e| e.timestamp());
+    let entries = &entries[entries.len().saturating_sub(limit)..];
+    emit(
+        format,
+        || {
+            Ok(HistoryReport {
+                version: SCHEMA_VERSION,
+                entries: entries
+                    .iter()
+                    .map(|entry| match entry {
+                        Entry::Commit(record) => EntryReport::Commit {
+                            timestamp: record.timestamp,
+                            sha: &record.sha,
+                            repo: repo_name(&record.repo_id),
+                            lines_added: record.lines_added,
+                            lines_deleted: record.lines_deleted,
+                            xp: record.xp,
+                            breakdown: &record.breakdown,
+                            active: allocation_report(&record.allocation),
+                        },
+                        Entry::Quest(reward) => EntryReport::Quest {
+                            timestamp: reward.timestamp,
+                            key: &reward.key,
+                            xp: reward.xp,
+                            active: allocation_report(&reward.allocation),
+                        },
+                        Entry::Respec(respec) => EntryReport::Respec {
+                            timestamp: respec.timestamp,
+                            from: respec.from,
+                            to: respec.to,
+                            amount: respec.amount,
+                            tax: respec.tax,
+                        },
+                    })
+                    .collect(),
+            })
+        },
+        || {
+            print_entries(entries, repo_name);
+            Ok(())
+        },
+    )
+}
+
+fn print_entries(entries: &[Entry], repo_name: impl Fn(&str) -> String) {
+    for entry in entries {
+        let date = DateTime::from_timestamp(entry.timestamp(), 0)
+            .map(|d| d.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        match entry {
+            Entry::Commit(record) => println!(
+                "{date}  {:<7}  {:<16} {:>8}  {}",
+                &record.sha[..record.sha.len().min(7)],
+                repo_name(&record.repo_id),
+                format!("+{} XP", record.xp),
+                record.allocation
+            ),
+            Entry::Quest(reward) => println!(
+                "{date}  {:<7}  {:<16} {:>8}  {}",
+                "quest",
+                quests::find(&reward.key).map_or("", |q| q.name),
+                format!("+{} XP", reward.xp),
+                reward.allocation
+            ),
+            Entry::Respec(respec) => println!(
+                "{date}  {:<7}  {:<16} {:>8}  {} XP moved from {:?} to {:?}",
+                "respec",
+                "",
+                format!("-{} XP", respec.tax),
+                respec.amount - respec.tax,
+                respec.from,
+                respec.to
+            ),
+        }
+    }
+    if entries.is_empty() {
+        println!("No XP has been awarded yet");
+    }
+}
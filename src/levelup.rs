@@ -0,0 +1,79 @@
+use crate::ascii::display_number_x;
+use crate::config::config;
+use crate::prestige::{next_threshold, prestige_multiplier};
+use crate::scaling::{SUB_STATS, XpType, calculate_level_info, multiplier};
+use crate::state::{Experience, read_prestige};
+use crate::theme::{bar_color, bold, paint};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Celebration {
+    /// The new level in big digits with everything it unlocked
+    Full,
+    /// A single line
+    Compact,
+}
+
+/// What going from level `before` to `level` gives, shown under the celebration
+fn rewards(stat: XpType, before: u32, level: u32) -> Vec<String> {
+    if stat != XpType::Total {
+        return vec![format!("multiplier is now {:.2}x", multiplier(level, stat))];
+    }
+    let prestige = read_prestige().unwrap_or(0);
+    // Several levels can be gained at once, jumping over the threshold itself
+    let threshold = next_threshold(prestige);
+    if before < threshold && level >= threshold {
+        vec![format!(
+            "Prestige {} unlocked, run git ascend prestige for a {:.2}x multiplier",
+            prestige + 1,
+            prestige_multiplier(prestige + 1)
+        )]
+    } else {
+        Vec::new()
+    }
+}
+
+/// Celebrate a stat reaching a new level while its progress bar animates
+pub fn celebrate(stat: XpType, before: u32, level: u32) {
+    let rewards = rewards(stat, before, level);
+    match config().animation.celebration {
+        Celebration::Compact => {
+            let mut line = format!("🎉 {stat:?} reached level {level}");
+            for reward in &rewards {
+                line.push_str(&format!(", {reward}"));
+            }
+            println!("{}", bold(&line));
+        }
+        Celebration::Full => {
+            let prestige = read_prestige().unwrap_or(0);
+            println!();
+            println!("{}", bold("🎉 LEVEL UP! 🎉"));
+            print!(
+                "{}",
+                paint(&display_number_x(level, prestige), bar_color(stat))
+            );
+            println!("{}", bold(&format!("{stat:?} reached level {level}")));
+            for reward in &rewards {
+                println!("✨ {reward}");
+            }
+            println!();
+        }
+    }
+}
+
+/// Announce the stats that levelled up between two points, Total only when its bar did not already celebrate it
+pub fn announce(pre: &Experience, post: &Experience, animated: bool) {
+    let total = (!animated).then_some(XpType::Total);
+    for stat in total.into_iter().chain(SUB_STATS) {
+        let before = calculate_level_info(pre.of(stat), stat).level;
+        let after = calculate_level_info(post.of(stat), stat).level;
+        if after > before {
+            let mut line = format!("⬆️ {stat:?} reached level {after}");
+            for reward in rewards(stat, before, after) {
+                line.push_str(&format!(", {reward}"));
+            }
+            println!("{line}");
+        }
+    }
+}
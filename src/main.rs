@@ -1,18 +1,58 @@
-use crate::git::GitRepo;
-use crate::progress::{animated_progress_bar, format_progress_bar};
-use crate::scaling::{XpType, calculate_level_info, total_xp_gain};
+use crate::achievements::list_achievements;
+use crate::allocation::{Allocation, parse_share};
+use crate::award::{
+    award_in_background, award_repo, display_award, display_breakdown, record_pending,
+    show_pending, sync,
+};
+use crate::badge::{BadgeTemplate, badge};
+use crate::curve::{CurveStyle, curve};
+use crate::dashboard::dashboard;
+use crate::doctor::doctor;
+use crate::heatmap::heatmap;
+use crate::history::history;
+use crate::output::{OutputFormat, emit};
+use crate::prestige::prestige;
+use crate::prompt::prompt;
+use crate::quests::list_quests;
+use crate::report::report;
+use crate::respec::respec;
+use crate::scaling::XpType;
 use crate::setup::{check_setup, first_run, setup, welcome_message};
-use crate::state::{inc_last_commit, inc_xp, read_xp, repo_state, reset_xp, set_current_stat};
-use crate::stats::{main_stats, xp_levels};
-use clap::{Parser, Subcommand};
+use crate::state::{lock_state, reset_xp, set_allocation};
+use crate::stats::{main_stats, stats_report, xp_levels};
+use crate::theme::interactive;
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser, Subcommand};
+use std::path::PathBuf;
 
+mod achievements;
+mod allocation;
 mod ascii;
+mod award;
+mod badge;
+mod caps;
+mod config;
+mod curve;
+mod dashboard;
+mod doctor;
 mod git;
+mod heatmap;
+mod history;
+mod levelup;
+mod output;
+mod policy;
+mod prestige;
 mod progress;
+mod prompt;
+mod quests;
+mod report;
+mod respec;
 mod scaling;
 mod setup;
 mod state;
 mod stats;
+mod streak;
+mod theme;
 
 /// Become the 1,000,000x developer you were destined to be
 #[derive(Parser)]
@@ -25,42 +65,242 @@ struct Cli {
     /// Disable progress bar animations after commit
     #[arg(short, long, action)]
     disable_animations: bool,
+    /// Only show the progress bar after a commit, without the breakdown of each commit's XP
+    #[arg(short, long, action)]
+    quiet: bool,
+    /// Collect XP in the background and show it on the next interactive run
+    #[arg(short, long, action)]
+    background: bool,
+    /// Override a configuration value for this run, e.g. progress.width=30
+    #[arg(short, long = "config", value_name = "KEY=VALUE")]
+    config_overrides: Vec<String>,
+    /// Print the output as plain text or in a machine readable format, for stats, history, curve
+    /// and the award
+    #[arg(long, value_enum, default_value = "plain", global = true)]
+    format: OutputFormat,
+    /// Used by background mode to collect XP without any output
+    #[arg(long, action, hide = true)]
+    record_pending: bool,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Add a git repository to your ascension
-    Setup,
+    Setup {
+        /// Install a hook that collects XP in the background so commits never block
+        #[arg(short, long, action)]
+        background: bool,
+    },
     /// View your experience levels and stat multipliers
     Stats,
-    /// Change your currently leveling stat
-    Switch { stat: Option<XpType> },
+    /// Full screen view of your levels, recent XP and repositories
+    Dashboard,
+    /// List achievements and when you unlocked them
+    Achievements,
+    /// Show today's quests and your progress towards them
+    Quests,
+    /// Change the stats you are leveling
+    Switch {
+        /// Stats to level and their share of XP in percent, e.g. output=50 pedantry=30 knowledge=20
+        #[arg(value_parser = parse_share)]
+        shares: Vec<(XpType, u32)>,
+    },
+    /// Move XP from one stat to another for a tax
+    Respec {
+        from: XpType,
+        to: XpType,
+        /// XP to move, all of it when omitted
+        amount: Option<u128>,
+        /// Skip the confirmation prompt
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+    /// List commits XP was awarded for and XP moved between stats
+    History {
+        /// Number of entries to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+    },
     /// Reset all experience levels
     Reset,
+    /// Trade all levels for a permanent XP multiplier
+    Prestige {
+        /// Skip the confirmation prompt
+        #[arg(short, long, action)]
+        yes: bool,
+    },
+    /// Collect XP from all registered repositories without using hooks
+    Sync,
+    /// Diagnose why commits are not awarding XP
+    Doctor,
+    /// Write an SVG badge of your levels for a README or website
+    Badge {
+        /// File to write, - prints the SVG
+        #[arg(short, long, default_value = "badge.svg")]
+        out: PathBuf,
+        #[arg(short, long, value_enum, default_value = "card")]
+        template: BadgeTemplate,
+    },
+    /// Show a calendar of the XP earned each day over the last year
+    Heatmap {
+        /// Only count commits in this repository, by directory name
+        #[arg(short, long)]
+        repo: Option<String>,
+        /// Only count XP this stat received
+        #[arg(short, long, default_value = "total")]
+        stat: XpType,
+    },
+    /// Write a yearly HTML report to share, with no external resources
+    Report {
+        /// Directory to write index.html to
+        #[arg(long)]
+        html: PathBuf,
+        /// Calendar year to report on instead of the last 365 days
+        #[arg(short, long)]
+        year: Option<i32>,
+    },
+    /// Print a short status line for shell prompts, reading a cached summary
+    Prompt {
+        /// Text to print, with {level}, {percent}, {stat} and {streak} filled in
+        #[arg(value_name = "FORMAT")]
+        template: Option<String>,
+    },
+    /// Show the XP needed for each level and the multipliers it gives
+    Curve {
+        /// Stat whose level curve to show
+        #[arg(default_value = "total")]
+        stat: XpType,
+        /// Highest level to show
+        #[arg(short, long, default_value_t = 100)]
+        max_level: u32,
+        /// Only show every nth level
+        #[arg(short, long, default_value_t = 1)]
+        step: u32,
+        /// How to print the levels when --format is plain
+        #[arg(long, value_enum, default_value = "table")]
+        style: CurveStyle,
+    },
+    /// View or change configuration
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the effective value of a setting
+    Get { key: String },
+    /// Store a setting in the config file
+    Set { key: String, value: String },
+    /// Remove a setting from the config file
+    Unset { key: String },
+    /// Print every effective setting
+    List,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
+    let machine_readable = matches!(
+        cli.command,
+        None | Some(Commands::Stats | Commands::History { .. } | Commands::Curve { .. })
+    );
+    if cli.format != OutputFormat::Plain && !machine_readable {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--format is only supported by stats, history, curve and the award",
+            )
+            .exit();
+    }
+    // Both must keep working while the config is broken, to repair or diagnose it
+    if !matches!(
+        cli.command,
+        Some(Commands::Config { .. } | Commands::Doctor)
+    ) {
+        config::init(&cli.config_overrides)?;
+    }
     let repo_path = cli.repo_path;
+    // Frames drawn with \r only make sense on a terminal, not in pipes or GUI git clients
+    let animate = !cli.disable_animations && interactive();
     match cli.command {
-        Some(Commands::Setup) => {
-            setup(&repo_path)?;
+        Some(Commands::Setup { background }) => {
+            setup(&repo_path, background)?;
         }
         Some(Commands::Reset) => {
-            reset_xp()?;
+            reset_xp(&lock_state()?)?;
             println!("XP reset to 0");
         }
+        Some(Commands::Respec {
+            from,
+            to,
+            amount,
+            yes,
+        }) => {
+            respec(from, to, amount, yes)?;
+        }
+        Some(Commands::History { limit }) => {
+            history(limit, cli.format)?;
+        }
+        Some(Commands::Prestige { yes }) => {
+            prestige(yes)?;
+        }
         Some(Commands::Stats) => {
-            main_stats()?;
-            xp_levels()?;
+            emit(cli.format, stats_report, || {
+                show_pending(animate)?;
+                main_stats()?;
+                xp_levels()
+            })?;
+        }
+        Some(Commands::Dashboard) => {
+            dashboard()?;
+        }
+        Some(Commands::Achievements) => {
+            list_achievements()?;
+        }
+        Some(Commands::Quests) => {
+            list_quests()?;
         }
-        Some(Commands::Switch { stat }) => {
-            let set_stat = match stat {
-                Some(stat) => stat,
-                None => query_stat(),
+        Some(Commands::Sync) => {
+            sync()?;
+        }
+        Some(Commands::Doctor) => {
+            doctor(&repo_path)?;
+        }
+        Some(Commands::Badge { out, template }) => {
+            badge(&out, template)?;
+        }
+        Some(Commands::Heatmap { repo, stat }) => {
+            heatmap(repo, stat)?;
+        }
+        Some(Commands::Report { html, year }) => {
+            report(&html, year)?;
+        }
+        Some(Commands::Prompt { template }) => {
+            prompt(template)?;
+        }
+        Some(Commands::Curve {
+            stat,
+            max_level,
+            step,
+            style,
+        }) => {
+            curve(stat, max_level, step, style, cli.format)?;
+        }
+        Some(Commands::Config { action }) => match action {
+            ConfigAction::Get { key } => config::get(&key, &cli.config_overrides)?,
+            ConfigAction::Set { key, value } => config::set(&key, &value)?,
+            ConfigAction::Unset { key } => config::unset(&key)?,
+            ConfigAction::List => config::list(&cli.config_overrides)?,
+        },
+        Some(Commands::Switch { shares }) => {
+            let allocation = if shares.is_empty() {
+                Allocation::single(query_stat())
+            } else {
+                Allocation::new(shares)?
             };
-            set_current_stat(set_stat)?;
-            println!("Current stat set to {set_stat:?}");
+            set_allocation(&lock_state()?, allocation.clone())?;
+            println!("Now leveling {allocation}");
         }
         None => {
             if first_run() {
@@ -72,42 +312,33 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 );
                 return Ok(());
             }
-            let repo = GitRepo::new(&repo_path)?;
-            let repo_id = repo.id()?;
-            let repo_state = repo_state(&repo_id)?;
-            let pre_exp = read_xp()?;
-            let stats = repo.commits_since(&repo_state.last_recorded_commit)?;
-            let post_exp;
-            if !stats.is_empty() {
-                let total_added: u32 = stats.iter().map(|s| s.lines_added).sum();
-                let total_deleted: u32 = stats.iter().map(|s| s.lines_deleted).sum();
-                let commit_msg_len: u32 = stats.iter().map(|s| s.message.len() as u32).sum();
-                let total = total_xp_gain(total_added, total_deleted, commit_msg_len)?;
-                post_exp = inc_xp(total)?;
-                inc_last_commit(&repo_id, &stats.first().unwrap().sha)?;
-            } else {
-                post_exp = read_xp()?;
+            if cli.record_pending {
+                record_pending(&repo_path)?;
+                return Ok(());
+            } else if cli.background {
+                award_in_background(&repo_path)?;
+                return Ok(());
             }
-            if cli.disable_animations {
-                let info = calculate_level_info(post_exp.total, XpType::Total);
-                let cur_bar = format_progress_bar(
-                    info.current_level_progress,
-                    info.xp_needed_to_level,
-                    None,
-                    None,
-                );
-                print!("{}x {}", info.level, cur_bar);
-            } else {
-                animated_progress_bar(pre_exp.total, post_exp.total, None, |total_xp| {
-                    let info = calculate_level_info(total_xp, XpType::Total);
-                    (
-                        info.current_level_progress,
-                        info.xp_needed_to_level,
-                        info.level,
-                    )
-                });
+            // Pending XP is kept for the next plain run so scripts don't swallow it
+            if cli.format == OutputFormat::Plain {
+                show_pending(animate)?;
             }
-            println!();
+            let award = award_repo(&lock_state()?, &repo_path)?;
+            emit(
+                cli.format,
+                || Ok(award.report()),
+                || {
+                    if !cli.quiet {
+                        display_breakdown(&award.commits);
+                    }
+                    display_award(award.pre_exp.total, award.post_exp.total, animate);
+                    levelup::announce(&award.pre_exp, &award.post_exp, animate);
+                    achievements::announce(&award.achievements);
+                    quests::announce(&award.quests);
+                    caps::announce(&award.caps);
+                    Ok(())
+                },
+            )?;
         }
     }
 
@@ -0,0 +1,74 @@
+use crate::allocation::Allocation;
+use crate::scaling::{XpType, calculate_level_info, multiplier};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// Version of the JSON and YAML schemas, bumped whenever a field changes meaning or is removed
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    Plain,
+    Json,
+    Yaml,
+}
+
+/// Print a command's output, building the machine readable report only when it is asked for
+pub fn emit<T: Serialize>(
+    format: OutputFormat,
+    report: impl FnOnce() -> Result<T>,
+    plain: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    match format {
+        OutputFormat::Plain => plain()?,
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report()?)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(&report()?)?),
+    }
+    Ok(())
+}
+
+/// Where a stat stands on its level curve
+#[derive(Debug, Serialize)]
+pub struct LevelReport {
+    pub level: u32,
+    pub xp: u128,
+    /// XP earned towards the next level
+    pub progress: u128,
+    /// XP the next level takes in total
+    pub needed: u128,
+    /// Multiplier the stat gives, absent for Total
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub multiplier: Option<f64>,
+}
+
+impl LevelReport {
+    pub fn new(xp: u128, stat: XpType) -> Self {
+        let info = calculate_level_info(xp, stat);
+        LevelReport {
+            level: info.level,
+            xp,
+            progress: info.current_level_progress,
+            needed: info.xp_needed_to_level,
+            multiplier: (stat != XpType::Total).then(|| multiplier(info.level, stat)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShareReport {
+    pub stat: XpType,
+    pub percent: u32,
+}
+
+pub fn allocation_report(allocation: &Allocation) -> Vec<ShareReport> {
+    allocation
+        .shares()
+        .iter()
+        .map(|(stat, percent)| ShareReport {
+            stat: *stat,
+            percent: *percent,
+        })
+        .collect()
+}
@@ -0,0 +1,193 @@
+use crate::git::{CommitStats, GitRepo};
+use crate::scaling::XpType;
+use anyhow::{Context, Result, anyhow};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Name of the optional policy file read from the repository tree at HEAD
+pub const POLICY_FILE: &str = ".git-ascend.toml";
+/// Largest weight a stat can be given, keeping XP and levels within reach
+const MAX_WEIGHT: f64 = 100.0;
+
+/// Rules deciding how commits in a repository are scored
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// Glob patterns of files whose changes earn no XP
+    pub exclude: Vec<String>,
+    /// Stats that earn no XP in this repository
+    pub disabled_stats: Vec<XpType>,
+    pub weights: Weights,
+    pub message: MessageConvention,
+}
+
+/// Multipliers applied to the base XP of each stat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Weights {
+    pub output: f64,
+    pub pedantry: f64,
+    pub precision: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights {
+            output: 1.0,
+            pedantry: 1.0,
+            precision: 1.0,
+        }
+    }
+}
+
+impl Weights {
+    /// Every weight must be a number between 0 and `MAX_WEIGHT`, keys are named after `prefix`
+    pub fn validate(&self, prefix: &str) -> Result<(), (String, String)> {
+        for (key, value) in [
+            ("output", self.output),
+            ("pedantry", self.pedantry),
+            ("precision", self.precision),
+        ] {
+            if !(value.is_finite() && (0.0..=MAX_WEIGHT).contains(&value)) {
+                return Err((
+                    format!("{prefix}weights.{key}"),
+                    format!("must be between 0 and {MAX_WEIGHT}"),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Commit message rules, messages breaking them earn no Precision XP
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MessageConvention {
+    /// Allowed message prefixes such as "feat" or "fix", any prefix is accepted when empty
+    pub prefixes: Vec<String>,
+    /// Messages shorter than this are not counted
+    pub min_length: u32,
+}
+
+/// A repository's policy file, settings it leaves out keep the value from the config
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RepoPolicy {
+    exclude: Vec<String>,
+    disabled_stats: Vec<XpType>,
+    weights: RepoWeights,
+    message: RepoMessageConvention,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RepoWeights {
+    output: Option<f64>,
+    pedantry: Option<f64>,
+    precision: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RepoMessageConvention {
+    prefixes: Option<Vec<String>>,
+    min_length: Option<u32>,
+}
+
+/// Line and message totals of a commit after the policy has been applied
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScoredCommit {
+    pub lines_added: u32,
+    pub lines_deleted: u32,
+    pub message_len: u32,
+}
+
+impl Policy {
+    /// Read the repository's policy file and layer it over this policy
+    pub fn with_repo(&self, repo: &GitRepo) -> Result<Policy> {
+        match repo.file_at_head(POLICY_FILE)? {
+            Some(content) => {
+                let repo_policy: RepoPolicy =
+                    toml::from_str(&content).with_context(|| format!("Invalid {POLICY_FILE}"))?;
+                let merged = self.merge(repo_policy);
+                merged.weights.validate("").map_err(|(key, e)| {
+                    anyhow!("Invalid value for `{key}` in {POLICY_FILE}: {e}")
+                })?;
+                Ok(merged)
+            }
+            None => Ok(self.clone()),
+        }
+    }
+
+    /// Layer a repository's policy over this one, exclusions and disabled stats
+    /// add up while every weight and message rule it sets wins
+    pub fn merge(&self, other: RepoPolicy) -> Policy {
+        let mut merged = self.clone();
+        merged.exclude.extend(other.exclude);
+        for stat in other.disabled_stats {
+            if !merged.disabled_stats.contains(&stat) {
+                merged.disabled_stats.push(stat);
+            }
+        }
+        let weights = &mut merged.weights;
+        weights.output = other.weights.output.unwrap_or(weights.output);
+        weights.pedantry = other.weights.pedantry.unwrap_or(weights.pedantry);
+        weights.precision = other.weights.precision.unwrap_or(weights.precision);
+        let message = &mut merged.message;
+        if let Some(prefixes) = other.message.prefixes {
+            message.prefixes = prefixes;
+        }
+        message.min_length = other.message.min_length.unwrap_or(message.min_length);
+        merged
+    }
+
+    pub fn is_disabled(&self, stat: XpType) -> bool {
+        self.disabled_stats.contains(&stat)
+    }
+
+    /// Apply exclusions and message conventions to each commit
+    pub fn score(&self, commits: &[CommitStats]) -> Result<Vec<ScoredCommit>> {
+        let excluded = self.exclude_set()?;
+        Ok(commits
+            .iter()
+            .map(|commit| {
+                let files = commit.files.iter().filter(|f| !excluded.is_match(&f.path));
+                let (lines_added, lines_deleted) =
+                    files.fold((0, 0), |(a, d), f| (a + f.lines_added, d + f.lines_deleted));
+                let message_len = if self.message_follows_convention(&commit.message) {
+                    commit.message.len() as u32
+                } else {
+                    0
+                };
+                ScoredCommit {
+                    lines_added,
+                    lines_deleted,
+                    message_len,
+                }
+            })
+            .collect())
+    }
+
+    fn exclude_set(&self) -> Result<GlobSet> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.exclude {
+            builder.add(
+                Glob::new(pattern).with_context(|| format!("Invalid exclude pattern {pattern}"))?,
+            );
+        }
+        Ok(builder.build()?)
+    }
+
+    fn message_follows_convention(&self, message: &str) -> bool {
+        let message = message.trim();
+        if (message.len() as u32) < self.message.min_length {
+            return false;
+        }
+        self.message.prefixes.is_empty()
+            || self.message.prefixes.iter().any(|prefix| {
+                message
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.starts_with([':', '(', '!']))
+            })
+    }
+}
@@ -0,0 +1,98 @@
+use crate::config::config;
+use crate::scaling::{XpType, calculate_level_info};
+use crate::state::{inc_prestige, lock_state, read_prestige, read_xp};
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrestigeConfig {
+    /// Total level needed for each prestige, the last entry repeats
+    pub thresholds: Vec<u32>,
+    /// Multiplier added to all XP for every prestige
+    pub bonus: f64,
+}
+
+impl Default for PrestigeConfig {
+    fn default() -> Self {
+        PrestigeConfig {
+            thresholds: vec![100, 150, 200],
+            bonus: 0.25,
+        }
+    }
+}
+
+impl PrestigeConfig {
+    pub fn validate(&self) -> Result<(), (String, String)> {
+        let invalid = |key: &str, reason: &str| Err((key.to_string(), reason.to_string()));
+        if self.thresholds.is_empty() || self.thresholds.contains(&0) {
+            return invalid("prestige.thresholds", "must list levels of at least 1");
+        }
+        if self.thresholds.windows(2).any(|w| w[1] < w[0]) {
+            return invalid("prestige.thresholds", "must not decrease");
+        }
+        if !(self.bonus.is_finite() && self.bonus >= 0.0) {
+            return invalid("prestige.bonus", "must not be negative");
+        }
+        Ok(())
+    }
+}
+
+/// Total level required to reach the next prestige
+pub fn next_threshold(prestige: u32) -> u32 {
+    let thresholds = &config().prestige.thresholds;
+    let idx = (prestige as usize).min(thresholds.len() - 1);
+    thresholds[idx]
+}
+
+/// Permanent multiplier applied to all XP gained
+pub fn prestige_multiplier(prestige: u32) -> f64 {
+    1.0 + prestige as f64 * config().prestige.bonus
+}
+
+/// Current prestige count, if the Total level is high enough for the next one
+fn eligible_prestige() -> Result<u32> {
+    let prestige = read_prestige()?;
+    let level = calculate_level_info(read_xp()?.total, XpType::Total).level;
+    let required = next_threshold(prestige);
+    if level < required {
+        return Err(anyhow!(
+            "Prestige {} requires Total level {required}, you are level {level}",
+            prestige + 1
+        ));
+    }
+    Ok(prestige)
+}
+
+/// Trade every level for a permanent multiplier once the Total level is high enough
+pub fn prestige(confirmed: bool) -> Result<()> {
+    let prestige = eligible_prestige()?;
+    if !confirmed {
+        println!(
+            "Prestige resets all of your levels to 0 and raises your permanent multiplier from {:.2}x to {:.2}x.",
+            prestige_multiplier(prestige),
+            prestige_multiplier(prestige + 1)
+        );
+        println!("Continue? [y/N]");
+        let mut input_str = String::new();
+        std::io::stdin().read_line(&mut input_str)?;
+        if !input_str.trim().eq_ignore_ascii_case("y") {
+            println!("Prestige cancelled");
+            return Ok(());
+        }
+    }
+
+    // Ask before locking so other commands are not held up, then make sure nothing changed meanwhile
+    let lock = lock_state()?;
+    if eligible_prestige()? != prestige {
+        return Err(anyhow!(
+            "Prestige changed while waiting for confirmation, run the command again"
+        ));
+    }
+    let prestige = inc_prestige(&lock)?;
+    println!(
+        "You have ascended to prestige {prestige}! All XP is now multiplied by {:.2}x.",
+        prestige_multiplier(prestige)
+    );
+    Ok(())
+}
@@ -1,12 +1,56 @@
+use crate::config::config;
+use crate::levelup::celebrate;
+use crate::scaling::XpType;
+use crate::theme::{Color, bar_color, interactive, paint};
+use ratatui::crossterm::terminal;
+use serde::{Deserialize, Serialize};
 use std::io::{self, Write};
 
+/// Columns left around the Total bar for its level and percentage
+const TOTAL_BAR_MARGIN: u32 = 32;
+/// Columns left around sub stat bars for the stat name, level, percentage and multiplier
+const SHORT_BAR_MARGIN: u32 = 44;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BarStyle {
+    /// `filled_char` and `empty_char` repeated
+    Blocks,
+    /// Plain ASCII for consoles without UTF-8
+    Ascii,
+    /// Block eighths, precise to an eighth of a character
+    Eighths,
+    /// Braille dots, precise to half a character
+    Braille,
+    /// Shades that darken towards the end of the bar
+    Gradient,
+}
+
+/// Width of a bar after shrinking it to fit the terminal, `None` is the Total bar
+pub fn bar_width(width: Option<u32>) -> u32 {
+    let progress = &config().progress;
+    let (width, margin) = match width {
+        Some(width) => (width, SHORT_BAR_MARGIN),
+        None => (progress.width, TOTAL_BAR_MARGIN),
+    };
+    if !progress.fit_terminal || !interactive() {
+        return width;
+    }
+    match terminal::size() {
+        Ok((columns, _)) => width.min((columns as u32).saturating_sub(margin)).max(5),
+        Err(_) => width,
+    }
+}
+
+/// Progress bar coloured by the theme when it belongs to a stat
 pub fn format_progress_bar(
     current: u128,
     max: u128,
     width: Option<u32>,
     label: Option<&str>,
+    stat: Option<XpType>,
 ) -> String {
-    let bar_width = width.unwrap_or(50);
+    let bar_width = bar_width(width) as usize;
     let label_text = label.unwrap_or("");
 
     let percentage = if max == 0 {
@@ -14,45 +58,79 @@ pub fn format_progress_bar(
     } else {
         (current as f64 / max as f64) * 100.0
     };
-    let filled_width = if max == 0 {
-        0
+    let fraction = if max == 0 {
+        0.0
     } else {
-        (current * bar_width as u128) / max
+        (current as f64 / max as f64).clamp(0.0, 1.0)
     };
+    let (filled, empty) = render_bar(fraction, bar_width);
+    let filled_part = paint(&filled, stat.map_or(Color::None, bar_color));
 
-    let filled_char = '█';
-    let empty_char = '░';
+    format!("{label_text}{filled_part}{empty} {percentage:>4.1}% ({current}/{max})")
+}
 
-    let filled_part: String = filled_char.to_string().repeat(filled_width as usize);
-    let empty_part: String = empty_char
-        .to_string()
-        .repeat(bar_width.wrapping_sub(filled_width.try_into().unwrap_or(0)) as usize);
+/// Filled and empty parts of a bar in the configured style
+pub fn render_bar(fraction: f64, width: usize) -> (String, String) {
+    let progress = &config().progress;
+    // Cells filled, with the fraction of the cell after them that is filled too
+    let cells = fraction * width as f64;
+    let full = (cells.floor() as usize).min(width);
+    let partial = cells - full as f64;
+    let partial_cell = |glyphs: &[char]| {
+        let idx = (partial * glyphs.len() as f64) as usize;
+        (full < width && idx > 0).then(|| glyphs[idx.min(glyphs.len() - 1)])
+    };
 
-    format!("{label_text}{filled_part}{empty_part} {percentage:>4.1}% ({current}/{max})")
+    let (filled, partial, empty): (String, Option<char>, &str) = match progress.style {
+        BarStyle::Blocks => (
+            progress.filled_char.repeat(full),
+            None,
+            &progress.empty_char,
+        ),
+        BarStyle::Ascii => ("#".repeat(full), None, "-"),
+        BarStyle::Eighths => (
+            "█".repeat(full),
+            partial_cell(&[' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉']),
+            " ",
+        ),
+        BarStyle::Braille => ("⣿".repeat(full), partial_cell(&['⣀', '⡇']), "⣀"),
+        BarStyle::Gradient => {
+            const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+            let filled = (0..full)
+                .map(|i| SHADES[i * SHADES.len() / width.max(1)])
+                .collect();
+            (filled, None, " ")
+        }
+    };
+    let used = full + partial.is_some() as usize;
+    let filled = filled + &partial.map(String::from).unwrap_or_default();
+    (filled, empty.repeat(width - used))
 }
 
 pub fn animated_progress_bar(
     from: u128,
     to: u128,
     label: Option<&str>,
+    stat: XpType,
     cb: fn(total_xp: u128) -> (u128, u128, u32),
 ) {
     let total_range = to - from;
 
-    const MAX_FRAMES: u128 = 50; // Limit frames for performance
-    const FRAME_DELAY_MS: u64 = 10; // Consistent delay
+    let animation = &config().animation;
+    let max_frames = animation.frames as u128; // Limit frames for performance
+    let frame_delay_ms = animation.frame_delay_ms; // Consistent delay
 
-    let frames_to_show = if total_range <= MAX_FRAMES {
+    let frames_to_show = if total_range <= max_frames {
         // Show every frame if range is small
         (from..=to).collect::<Vec<_>>()
     } else {
-        // Sample 50 frames
+        // Sample a fixed number of frames
         let mut frames = Vec::new();
         frames.push(from);
 
         // Add intermediate frames
-        for i in 1..MAX_FRAMES {
-            let value = from + (total_range * i) / MAX_FRAMES;
+        for i in 1..max_frames {
+            let value = from + (total_range * i) / max_frames;
             if value != from && value != to {
                 frames.push(value);
             }
@@ -62,9 +140,19 @@ pub fn animated_progress_bar(
         frames
     };
 
+    let mut last = cb(from);
     for (i, &value) in frames_to_show.iter().enumerate() {
         let (progress, required, level) = cb(value);
-        let cur_bar = format_progress_bar(progress, required, None, label);
+        if level > last.2 {
+            // Fill the old level's bar before celebrating instead of wrapping back to 0
+            let (_, last_required, last_level) = last;
+            let full_bar =
+                format_progress_bar(last_required, last_required, None, label, Some(stat));
+            println!("\r{last_level}x {full_bar}");
+            celebrate(stat, last_level, level);
+        }
+        last = (progress, required, level);
+        let cur_bar = format_progress_bar(progress, required, None, label, Some(stat));
         print!("{level}x {cur_bar}");
 
         if i != frames_to_show.len() - 1 {
@@ -72,11 +160,17 @@ pub fn animated_progress_bar(
         }
 
         io::stdout().flush().unwrap();
-        std::thread::sleep(std::time::Duration::from_millis(FRAME_DELAY_MS));
+        std::thread::sleep(std::time::Duration::from_millis(frame_delay_ms));
     }
 }
 
-pub fn short_bar_outside_label(current: u128, max: u128, label: &str) -> String {
-    let progress_bar = format_progress_bar(current, max, Some(25), None);
+pub fn short_bar_outside_label(current: u128, max: u128, label: &str, stat: XpType) -> String {
+    let progress_bar = format_progress_bar(
+        current,
+        max,
+        Some(config().progress.short_width),
+        None,
+        Some(stat),
+    );
     format!("{label:>3} {progress_bar}")
 }
@@ -0,0 +1,95 @@
+use crate::allocation::Allocation;
+use crate::config::config;
+use crate::scaling::{XpType, calculate_level_info};
+use crate::setup::{data_location, first_run};
+use crate::state::{CommitRecord, Experience, lock_state, refresh_summary, write_atomic};
+use crate::streak::{alive, streak};
+use anyhow::{Context, Result};
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PromptConfig {
+    /// Text printed by `git ascend prompt`, with {level}, {percent}, {stat} and {streak} filled in
+    pub format: String,
+}
+
+impl Default for PromptConfig {
+    fn default() -> Self {
+        PromptConfig {
+            format: "{level}x {percent}% {stat} {streak}d".to_string(),
+        }
+    }
+}
+
+/// Everything the prompt shows, cached whenever the state changes so prompts never decode it
+#[derive(Debug, Serialize, Deserialize)]
+struct Summary {
+    level: u32,
+    /// Progress towards the next Total level
+    percent: u32,
+    stat: String,
+    streak: u32,
+    /// Day of the newest commit, so a streak that has since broken shows as 0
+    last_commit: Option<NaiveDate>,
+}
+
+fn summary_path() -> PathBuf {
+    Path::new(&data_location()).join("summary.json")
+}
+
+pub fn write_summary(
+    experience: &Experience,
+    allocation: &Allocation,
+    history: &[CommitRecord],
+) -> Result<()> {
+    let info = calculate_level_info(experience.total, XpType::Total);
+    let summary = Summary {
+        level: info.level,
+        percent: (info.current_level_progress * 100)
+            .checked_div(info.xp_needed_to_level)
+            .unwrap_or(0) as u32,
+        stat: allocation.to_string(),
+        streak: streak(history, Local::now().date_naive()).current,
+        last_commit: history.iter().map(|r| r.local_date()).max(),
+    };
+    write_atomic(&summary_path(), serde_json::to_string(&summary)?.as_bytes())
+        .context("Could not write summary file")?;
+    Ok(())
+}
+
+fn read_summary() -> Result<Summary> {
+    let summary = fs::read_to_string(summary_path()).context("Could not read summary file")?;
+    Ok(serde_json::from_str(&summary)?)
+}
+
+/// Print a short status line for shell prompts and status bars
+pub fn prompt(format: Option<String>) -> Result<()> {
+    let summary = match read_summary() {
+        Ok(summary) => summary,
+        // Nothing to show before setup, and prompts must not print errors
+        Err(_) if first_run() => return Ok(()),
+        // Missing after an upgrade or written by another version
+        Err(_) => {
+            refresh_summary(&lock_state()?)?;
+            read_summary()?
+        }
+    };
+    let streak = match summary.last_commit {
+        Some(last) if alive(last, Local::now().date_naive()) => summary.streak,
+        _ => 0,
+    };
+    let format = format.unwrap_or_else(|| config().prompt.format.clone());
+    println!(
+        "{}",
+        format
+            .replace("{level}", &summary.level.to_string())
+            .replace("{percent}", &summary.percent.to_string())
+            .replace("{stat}", &summary.stat)
+            .replace("{streak}", &streak.to_string())
+    );
+    Ok(())
+}
@@ -0,0 +1,295 @@
+use crate::config::config;
+use crate::git::git_username;
+use crate::progress::format_progress_bar;
+use crate::state::{CommitRecord, QuestReward, State, read_completed_quests, read_history};
+use crate::theme::bold;
+use anyhow::Result;
+use chrono::{Datelike, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuestConfig {
+    /// Number of daily quests offered each day
+    pub daily: u32,
+    /// XP awarded for completing a daily quest
+    pub daily_bonus: u64,
+    /// XP awarded for completing a weekly quest
+    pub weekly_bonus: u64,
+}
+
+impl Default for QuestConfig {
+    fn default() -> Self {
+        QuestConfig {
+            daily: 2,
+            daily_bonus: 100,
+            weekly_bonus: 500,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Period {
+    Daily,
+    Weekly,
+}
+
+pub struct Quest {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub period: Period,
+    pub goal: u32,
+    /// Progress towards the goal made by the commits of the quest's period
+    progress: fn(commits: &[&CommitRecord]) -> u32,
+}
+
+static QUESTS: [Quest; 10] = [
+    Quest {
+        id: "tidy_up",
+        name: "Tidy Up",
+        description: "Delete more lines than you add today",
+        period: Period::Daily,
+        goal: 1,
+        progress: |commits| {
+            let added: u32 = commits.iter().map(|c| c.lines_added).sum();
+            let deleted: u32 = commits.iter().map(|c| c.lines_deleted).sum();
+            (!commits.is_empty() && deleted > added) as u32
+        },
+    },
+    Quest {
+        id: "raconteur",
+        name: "Raconteur",
+        description: "Write three commits with a body today",
+        period: Period::Daily,
+        goal: 3,
+        progress: |commits| commits.iter().filter(|c| c.has_body).count() as u32,
+    },
+    Quest {
+        id: "test_pilot",
+        name: "Test Pilot",
+        description: "Touch a test file today",
+        period: Period::Daily,
+        goal: 1,
+        progress: |commits| commits.iter().filter(|c| c.touched_tests).count() as u32,
+    },
+    Quest {
+        id: "busy_bee",
+        name: "Busy Bee",
+        description: "Make five commits today",
+        period: Period::Daily,
+        goal: 5,
+        progress: |commits| commits.len() as u32,
+    },
+    Quest {
+        id: "wordsmith",
+        name: "Wordsmith",
+        description: "Write a commit message of at least 72 characters today",
+        period: Period::Daily,
+        goal: 1,
+        progress: |commits| commits.iter().filter(|c| c.message_len >= 72).count() as u32,
+    },
+    Quest {
+        id: "marathon",
+        name: "Marathon",
+        description: "Commit on five different days this week",
+        period: Period::Weekly,
+        goal: 5,
+        progress: |commits| {
+            let mut days: Vec<NaiveDate> = commits.iter().map(|c| c.local_date()).collect();
+            days.sort();
+            days.dedup();
+            days.len() as u32
+        },
+    },
+    Quest {
+        id: "demolition",
+        name: "Demolition",
+        description: "Delete 500 lines this week",
+        period: Period::Weekly,
+        goal: 500,
+        progress: |commits| commits.iter().map(|c| c.lines_deleted).sum(),
+    },
+    Quest {
+        id: "architect",
+        name: "Architect",
+        description: "Add 1,000 lines this week",
+        period: Period::Weekly,
+        goal: 1000,
+        progress: |commits| commits.iter().map(|c| c.lines_added).sum(),
+    },
+    Quest {
+        id: "chronicler",
+        name: "Chronicler",
+        description: "Write ten commits with a body this week",
+        period: Period::Weekly,
+        goal: 10,
+        progress: |commits| commits.iter().filter(|c| c.has_body).count() as u32,
+    },
+    Quest {
+        id: "quality_control",
+        name: "Quality Control",
+        description: "Touch test files in five commits this week",
+        period: Period::Weekly,
+        goal: 5,
+        progress: |commits| commits.iter().filter(|c| c.touched_tests).count() as u32,
+    },
+];
+
+impl Quest {
+    pub fn bonus(&self) -> u128 {
+        match self.period {
+            Period::Daily => config().quests.daily_bonus as u128,
+            Period::Weekly => config().quests.weekly_bonus as u128,
+        }
+    }
+
+    /// Whether a commit made on `day` counts towards this quest when offered on `today`
+    fn in_period(&self, day: NaiveDate, today: NaiveDate) -> bool {
+        match self.period {
+            Period::Daily => day == today,
+            Period::Weekly => day.iso_week() == today.iso_week(),
+        }
+    }
+}
+
+/// A quest on offer and how far along it is
+pub struct ActiveQuest {
+    pub quest: &'static Quest,
+    /// Identifies this quest in this period so it is only rewarded once
+    pub key: String,
+    pub progress: u32,
+    pub completed: bool,
+}
+
+pub fn find(key: &str) -> Option<&'static Quest> {
+    let id = key.rsplit(':').next()?;
+    QUESTS.iter().find(|q| q.id == id)
+}
+
+/// Quests on offer today, the same for a given user and date
+fn offered(today: NaiveDate) -> Vec<(&'static Quest, String)> {
+    let user = git_username().unwrap_or_default();
+    let week = today.iso_week();
+    let day_period = today.format("%Y-%m-%d").to_string();
+    let week_period = format!("{}-W{:02}", week.year(), week.week());
+
+    let daily = pick(
+        Period::Daily,
+        &format!("{user}:{day_period}"),
+        config().quests.daily,
+    );
+    let weekly = pick(Period::Weekly, &format!("{user}:{week_period}"), 1);
+    daily
+        .into_iter()
+        .map(|q| (q, format!("{day_period}:{}", q.id)))
+        .chain(
+            weekly
+                .into_iter()
+                .map(|q| (q, format!("{week_period}:{}", q.id))),
+        )
+        .collect()
+}
+
+/// Deterministically choose `count` quests of a period from a seed
+fn pick(period: Period, seed: &str, count: u32) -> Vec<&'static Quest> {
+    let mut pool: Vec<&'static Quest> = QUESTS.iter().filter(|q| q.period == period).collect();
+    let mut state = fnv1a(seed);
+    let mut picked = Vec::new();
+    while !pool.is_empty() && picked.len() < count as usize {
+        // xorshift keeps the sequence stable across platforms and releases
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        picked.push(pool.remove((state % pool.len() as u64) as usize));
+    }
+    picked
+}
+
+fn fnv1a(input: &str) -> u64 {
+    input.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn active_quests(
+    history: &[CommitRecord],
+    completed: &[String],
+    today: NaiveDate,
+) -> Vec<ActiveQuest> {
+    offered(today)
+        .into_iter()
+        .map(|(quest, key)| {
+            let commits: Vec<&CommitRecord> = history
+                .iter()
+                .filter(|c| quest.in_period(c.local_date(), today))
+                .collect();
+            ActiveQuest {
+                quest,
+                progress: (quest.progress)(&commits).min(quest.goal),
+                completed: completed.contains(&key),
+                key,
+            }
+        })
+        .collect()
+}
+
+/// Reward every quest on offer whose goal has been reached, returning the keys of newly completed quests
+pub fn evaluate(state: &mut State) -> Vec<String> {
+    let today = Local::now().date_naive();
+    let mut newly_completed = Vec::new();
+    for active in active_quests(&state.history, &state.completed_quests, today) {
+        if !active.completed && active.progress >= active.quest.goal {
+            state.add_xp(active.quest.bonus());
+            state.completed_quests.push(active.key.clone());
+            state.quest_rewards.push(QuestReward {
+                timestamp: Local::now().timestamp(),
+                key: active.key.clone(),
+                xp: active.quest.bonus(),
+                allocation: state.allocation.clone(),
+            });
+            newly_completed.push(active.key);
+        }
+    }
+    newly_completed
+}
+
+pub fn announce(keys: &[String]) {
+    for quest in keys.iter().filter_map(|key| find(key)) {
+        println!(
+            "⚔️  Quest complete: {} (+{} XP)",
+            bold(quest.name),
+            quest.bonus()
+        );
+    }
+}
+
+/// Show the quests on offer and progress towards them
+pub fn list_quests() -> Result<()> {
+    let active = active_quests(
+        &read_history()?,
+        &read_completed_quests()?,
+        Local::now().date_naive(),
+    );
+    for (period, title) in [(Period::Daily, "Daily"), (Period::Weekly, "Weekly")] {
+        println!("{}", bold(&format!("{title} quests")));
+        for active in active.iter().filter(|a| a.quest.period == period) {
+            let status = if active.completed {
+                "✔".to_string()
+            } else {
+                format!("+{} XP", active.quest.bonus())
+            };
+            println!("  {:<16} {}", active.quest.name, active.quest.description);
+            let bar = format_progress_bar(
+                active.progress as u128,
+                active.quest.goal as u128,
+                Some(config().progress.short_width),
+                None,
+                None,
+            );
+            println!("  {:<16} {bar} {status}", "");
+        }
+        println!();
+    }
+    Ok(())
+}
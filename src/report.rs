@@ -0,0 +1,365 @@
+use crate::achievements::ACHIEVEMENTS;
+use crate::badge::stat_color;
+use crate::history::{daily_xp, repo_name, repo_totals};
+use crate::scaling::{SUB_STATS, XpType};
+use crate::state::{
+    CommitRecord, QuestReward, RepoState, read_achievements, read_history, read_quest_rewards,
+    repos,
+};
+use crate::stats::{StatsReport, stats_report};
+use crate::streak::streak;
+use anyhow::{Context, Result, anyhow};
+use chrono::{Datelike, Duration as Days, Local, NaiveDate};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+/// Commits listed under top commits
+const TOP_COMMITS: usize = 10;
+
+const STYLE: &str = "
+body { font-family: system-ui, sans-serif; max-width: 900px; margin: 2em auto; padding: 0 1em; color: #1f2328; background: #fff; }
+h1 { margin-bottom: 0; }
+.muted { color: #656d76; }
+.cards { display: flex; gap: 1em; flex-wrap: wrap; margin: 1.5em 0; }
+.card { border: 1px solid #d0d7de; border-radius: 6px; padding: 0.8em 1.2em; }
+.card b { display: block; font-size: 1.6em; }
+table { border-collapse: collapse; width: 100%; }
+th, td { text-align: left; padding: 0.3em 0.6em; border-bottom: 1px solid #d0d7de; }
+td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+svg text { font-size: 10px; fill: #656d76; }
+.locked { color: #8c959f; }
+@media (prefers-color-scheme: dark) {
+  body { color: #e6edf3; background: #0d1117; }
+  .muted, svg text { color: #8d96a0; fill: #8d96a0; }
+  .card, th, td { border-color: #30363d; }
+}
+";
+
+/// Write a self-contained HTML page of the XP earned over a year
+pub fn report(out: &Path, year: Option<i32>) -> Result<()> {
+    let today = Local::now().date_naive();
+    let (title, first, last) = match year {
+        Some(year) => (
+            format!("Ascension wrapped {year}"),
+            NaiveDate::from_ymd_opt(year, 1, 1).ok_or_else(|| anyhow!("Invalid year {year}"))?,
+            NaiveDate::from_ymd_opt(year, 12, 31).ok_or_else(|| anyhow!("Invalid year {year}"))?,
+        ),
+        None => (
+            "Ascension wrapped".to_string(),
+            today - Days::days(364),
+            today,
+        ),
+    };
+    let history: Vec<CommitRecord> = read_history()?
+        .into_iter()
+        .filter(|r| (first..=last).contains(&r.local_date()))
+        .collect();
+    let rewards: Vec<QuestReward> = read_quest_rewards()?
+        .into_iter()
+        .filter(|r| (first..=last).contains(&r.local_date()))
+        .collect();
+    let repos = repos()?;
+    let stats = stats_report()?;
+
+    let mut html = String::new();
+    writeln!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>"
+    )?;
+    writeln!(
+        html,
+        "<h1>{title}</h1>\n<p class=\"muted\">{} to {}</p>",
+        first.format("%B %-d, %Y"),
+        last.format("%B %-d, %Y")
+    )?;
+    summary(&mut html, &history, &rewards, &stats, last)?;
+    writeln!(html, "<h2>XP over time</h2>")?;
+    timeline(&mut html, &history, &rewards, first, last)?;
+    writeln!(html, "<h2>Commit calendar</h2>")?;
+    calendar(&mut html, &history, &rewards, first, last)?;
+    writeln!(html, "<h2>Stats</h2>")?;
+    stat_table(&mut html, &history, &rewards, &stats)?;
+    writeln!(html, "<h2>Repositories</h2>")?;
+    repo_table(&mut html, &history, &repos)?;
+    writeln!(html, "<h2>Top commits</h2>")?;
+    top_commits(&mut html, &history, &repos)?;
+    writeln!(html, "<h2>Achievements</h2>")?;
+    achievements(&mut html, first, last)?;
+    writeln!(html, "</body>\n</html>")?;
+
+    fs::create_dir_all(out).with_context(|| format!("Could not create {}", out.display()))?;
+    let page = out.join("index.html");
+    fs::write(&page, html).with_context(|| format!("Could not write {}", page.display()))?;
+    println!("Report written to {}", page.display());
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn summary(
+    html: &mut String,
+    history: &[CommitRecord],
+    rewards: &[QuestReward],
+    stats: &StatsReport,
+    last: NaiveDate,
+) -> Result<()> {
+    let xp: u128 =
+        history.iter().map(|r| r.xp).sum::<u128>() + rewards.iter().map(|r| r.xp).sum::<u128>();
+    let added: u64 = history.iter().map(|r| r.lines_added as u64).sum();
+    let deleted: u64 = history.iter().map(|r| r.lines_deleted as u64).sum();
+    let cards = [
+        // Levels are only known as they are now, not at the end of a past period
+        ("Current level", format!("{}x developer", stats.total.level)),
+        ("XP earned", xp.to_string()),
+        ("Commits", history.len().to_string()),
+        ("Lines", format!("+{added} −{deleted}")),
+        (
+            "Longest streak",
+            format!("{} days", streak(history, last).longest),
+        ),
+    ];
+    writeln!(html, "<div class=\"cards\">")?;
+    for (label, value) in cards {
+        writeln!(
+            html,
+            "<div class=\"card\"><span class=\"muted\">{label}</span><b>{value}</b></div>"
+        )?;
+    }
+    writeln!(html, "</div>")?;
+    Ok(())
+}
+
+/// Cumulative XP earned over the period
+fn timeline(
+    html: &mut String,
+    history: &[CommitRecord],
+    rewards: &[QuestReward],
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<()> {
+    const WIDTH: f64 = 860.0;
+    const HEIGHT: f64 = 180.0;
+    let daily = daily_xp(history, rewards, first, last, XpType::Total);
+    let totals: Vec<u128> = daily
+        .iter()
+        .scan(0, |total, xp| {
+            *total += xp;
+            Some(*total)
+        })
+        .collect();
+    let total = totals.last().copied().unwrap_or(0);
+    let max = total.max(1) as f64;
+    let step = WIDTH / (totals.len().max(2) - 1) as f64;
+    let points: Vec<String> = totals
+        .iter()
+        .enumerate()
+        .map(|(i, total)| {
+            format!(
+                "{:.1},{:.1}",
+                i as f64 * step,
+                HEIGHT - *total as f64 / max * HEIGHT
+            )
+        })
+        .collect();
+    writeln!(
+        html,
+        "<svg viewBox=\"0 -10 {WIDTH} {}\" width=\"100%\" role=\"img\" aria-label=\"Cumulative XP\">\n<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"2\" points=\"{}\"/>\n<text x=\"0\" y=\"{}\">{}</text>\n<text x=\"{WIDTH}\" y=\"{}\" text-anchor=\"end\">{} · {total} XP</text>\n</svg>",
+        HEIGHT + 30.0,
+        stat_color(XpType::Total),
+        points.join(" "),
+        HEIGHT + 16.0,
+        first.format("%b %-d"),
+        HEIGHT + 16.0,
+        last.format("%b %-d"),
+    )?;
+    Ok(())
+}
+
+/// Grid of days coloured by XP, a column per week starting on Monday
+fn calendar(
+    html: &mut String,
+    history: &[CommitRecord],
+    rewards: &[QuestReward],
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<()> {
+    const CELL: i64 = 13;
+    let daily = daily_xp(history, rewards, first, last, XpType::Total);
+    let max = daily.iter().copied().max().unwrap_or(0);
+    let start = first - Days::days(first.weekday().num_days_from_monday() as i64);
+    let weeks = (last - start).num_days() / 7 + 1;
+    let color = stat_color(XpType::Total);
+    writeln!(
+        html,
+        "<svg viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\" aria-label=\"XP per day\">",
+        weeks * CELL + 30,
+        7 * CELL + 20
+    )?;
+    for (row, name) in [(0, "Mon"), (2, "Wed"), (4, "Fri")] {
+        writeln!(
+            html,
+            "<text x=\"0\" y=\"{}\">{name}</text>",
+            row * CELL + 30
+        )?;
+    }
+    for (i, xp) in daily.iter().enumerate() {
+        let day = first + Days::days(i as i64);
+        let offset = (day - start).num_days();
+        let (week, weekday) = (offset / 7, offset % 7);
+        if day.day() == 1 || i == 0 {
+            writeln!(
+                html,
+                "<text x=\"{}\" y=\"10\">{}</text>",
+                week * CELL + 30,
+                day.format("%b")
+            )?;
+        }
+        // Days without XP stay faint, the rest darken towards the busiest day
+        let opacity = if *xp == 0 {
+            0.08
+        } else {
+            0.25 + 0.75 * (*xp as f64 / max as f64)
+        };
+        writeln!(
+            html,
+            "<rect x=\"{}\" y=\"{}\" width=\"11\" height=\"11\" rx=\"2\" fill=\"{color}\" fill-opacity=\"{opacity:.2}\"><title>{}: {xp} XP</title></rect>",
+            week * CELL + 30,
+            weekday * CELL + 20,
+            day.format("%Y-%m-%d")
+        )?;
+    }
+    writeln!(html, "</svg>")?;
+    Ok(())
+}
+
+/// XP each stat earned from commits and XP each stat received from commits and quests
+fn stat_table(
+    html: &mut String,
+    history: &[CommitRecord],
+    rewards: &[QuestReward],
+    stats: &StatsReport,
+) -> Result<()> {
+    let received = history
+        .iter()
+        .map(|r| r.allocation.split(r.xp))
+        .chain(rewards.iter().map(|r| r.allocation.split(r.xp)));
+    let mut totals: HashMap<XpType, u128> = HashMap::new();
+    for (stat, xp) in received.flatten() {
+        *totals.entry(stat).or_default() += xp;
+    }
+    writeln!(
+        html,
+        "<table>\n<tr><th>Stat</th><th class=\"num\">Current level</th><th class=\"num\">Current multiplier</th><th class=\"num\">XP earned by commits</th><th class=\"num\">XP received</th></tr>"
+    )?;
+    for stat in SUB_STATS {
+        let level = stats.level(stat);
+        let earned = if stat == XpType::Knowledge {
+            "—".to_string()
+        } else {
+            format!(
+                "{:.0}",
+                history.iter().map(|r| r.breakdown.of(stat)).sum::<f64>()
+            )
+        };
+        writeln!(
+            html,
+            "<tr><td><span style=\"color: {}\">■</span> {stat:?}</td><td class=\"num\">{}</td><td class=\"num\">{:.2}x</td><td class=\"num\">{earned}</td><td class=\"num\">{}</td></tr>",
+            stat_color(stat),
+            level.level,
+            level.multiplier.unwrap_or(1.0),
+            totals.get(&stat).copied().unwrap_or(0)
+        )?;
+    }
+    writeln!(
+        html,
+        "</table>\n<p class=\"muted\">XP earned by commits is counted before the Knowledge, prestige and streak multipliers.</p>"
+    )?;
+    Ok(())
+}
+
+fn repo_table(
+    html: &mut String,
+    history: &[CommitRecord],
+    repos: &HashMap<String, RepoState>,
+) -> Result<()> {
+    writeln!(
+        html,
+        "<table>\n<tr><th>Repository</th><th class=\"num\">Commits</th><th class=\"num\">Lines</th><th class=\"num\">XP</th></tr>"
+    )?;
+    for (repo_id, totals) in repo_totals(history) {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">+{} −{}</td><td class=\"num\">{}</td></tr>",
+            escape(&repo_name(repos, repo_id)),
+            totals.commits,
+            totals.lines_added,
+            totals.lines_deleted,
+            totals.xp
+        )?;
+    }
+    writeln!(html, "</table>")?;
+    Ok(())
+}
+
+fn top_commits(
+    html: &mut String,
+    history: &[CommitRecord],
+    repos: &HashMap<String, RepoState>,
+) -> Result<()> {
+    let mut top: Vec<&CommitRecord> = history.iter().collect();
+    top.sort_by_key(|r| std::cmp::Reverse(r.xp));
+    writeln!(
+        html,
+        "<table>\n<tr><th>Date</th><th>Commit</th><th>Repository</th><th class=\"num\">Lines</th><th class=\"num\">XP</th></tr>"
+    )?;
+    for record in top.into_iter().take(TOP_COMMITS) {
+        writeln!(
+            html,
+            "<tr><td>{}</td><td><code>{}</code></td><td>{}</td><td class=\"num\">+{} −{}</td><td class=\"num\">{}</td></tr>",
+            record.local_date().format("%Y-%m-%d"),
+            escape(&record.sha.chars().take(7).collect::<String>()),
+            escape(&repo_name(repos, &record.repo_id)),
+            record.lines_added,
+            record.lines_deleted,
+            record.xp
+        )?;
+    }
+    writeln!(html, "</table>")?;
+    Ok(())
+}
+
+/// Every achievement, shown as unlocked if it was unlocked during the period
+fn achievements(html: &mut String, first: NaiveDate, last: NaiveDate) -> Result<()> {
+    let unlocked: Vec<_> = read_achievements()?
+        .into_iter()
+        .filter(|u| (first..=last).contains(&u.local_date()))
+        .collect();
+    writeln!(html, "<ul>")?;
+    for achievement in &ACHIEVEMENTS {
+        match unlocked.iter().find(|u| u.id == achievement.id) {
+            Some(u) => {
+                writeln!(
+                    html,
+                    "<li>🏆 <b>{}</b> - {} <span class=\"muted\">{}</span></li>",
+                    achievement.name,
+                    achievement.description,
+                    u.local_date()
+                )?;
+            }
+            None => writeln!(
+                html,
+                "<li class=\"locked\">🔒 {} - {}</li>",
+                achievement.name, achievement.description
+            )?,
+        }
+    }
+    writeln!(html, "</ul>")?;
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use crate::config::config;
+use crate::scaling::{XpType, calculate_level_info};
+use crate::state::{Respec, add_respec, lock_state, read_xp};
+use anyhow::{Result, anyhow};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RespecConfig {
+    /// Fraction of the moved XP that is lost
+    pub tax: f64,
+}
+
+impl Default for RespecConfig {
+    fn default() -> Self {
+        RespecConfig { tax: 0.1 }
+    }
+}
+
+impl RespecConfig {
+    pub fn validate(&self) -> Result<(), (String, String)> {
+        if !(self.tax.is_finite() && (0.0..=1.0).contains(&self.tax)) {
+            return Err((
+                "respec.tax".to_string(),
+                "must be between 0 and 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// XP a respec moves and the levels of both stats before and after it
+struct Plan {
+    amount: u128,
+    tax: u128,
+    from_levels: (u32, u32),
+    to_levels: (u32, u32),
+}
+
+/// Work out a respec from the current XP, moving everything `from` has when no amount is given
+fn plan(from: XpType, to: XpType, amount: Option<u128>) -> Result<Plan> {
+    let xp = read_xp()?;
+    let available = xp.of(from);
+    let amount = amount.unwrap_or(available);
+    if amount == 0 {
+        return Err(anyhow!("No {from:?} XP to move"));
+    }
+    if amount > available {
+        return Err(anyhow!("{from:?} only has {available} XP"));
+    }
+    let tax = (amount as f64 * config().respec.tax).round() as u128;
+
+    let level = |stat: XpType, xp: u128| calculate_level_info(xp, stat).level;
+    Ok(Plan {
+        amount,
+        tax,
+        from_levels: (level(from, available), level(from, available - amount)),
+        to_levels: (
+            level(to, xp.of(to)),
+            level(to, xp.of(to).saturating_add(amount - tax)),
+        ),
+    })
+}
+
+/// Move XP from one sub stat to another, losing the configured tax
+pub fn respec(from: XpType, to: XpType, amount: Option<u128>, confirmed: bool) -> Result<()> {
+    if from == XpType::Total || to == XpType::Total {
+        return Err(anyhow!("Total cannot be respecced, choose two sub stats"));
+    }
+    if from == to {
+        return Err(anyhow!("Choose two different stats"));
+    }
+    let preview = plan(from, to, amount)?;
+    if !confirmed {
+        println!(
+            "Moving {} XP from {from:?} to {to:?} costs {} XP. {from:?} goes from level {} to {}, {to:?} from level {} to {}.",
+            preview.amount,
+            preview.tax,
+            preview.from_levels.0,
+            preview.from_levels.1,
+            preview.to_levels.0,
+            preview.to_levels.1
+        );
+        println!("Continue? [y/N]");
+        let mut input_str = String::new();
+        std::io::stdin().read_line(&mut input_str)?;
+        if !input_str.trim().eq_ignore_ascii_case("y") {
+            println!("Respec cancelled");
+            return Ok(());
+        }
+    }
+
+    // Ask before locking so other commands are not held up, then check the confirmed amount
+    // against the XP as it is now
+    let lock = lock_state()?;
+    let Plan {
+        amount,
+        tax,
+        from_levels,
+        to_levels,
+    } = plan(from, to, Some(preview.amount))?;
+    add_respec(
+        &lock,
+        Respec {
+            timestamp: Local::now().timestamp(),
+            from,
+            to,
+            amount,
+            tax,
+        },
+    )?;
+    println!(
+        "Moved {} XP from {from:?} to {to:?} ({tax} XP tax). {from:?} is now level {}, {to:?} level {}.",
+        amount - tax,
+        from_levels.1,
+        to_levels.1
+    );
+    Ok(())
+}
@@ -1,14 +1,21 @@
-use crate::state::read_xp;
+use crate::caps::{Cap, CapKind, diminish, earned_on, soft_cap};
+use crate::config::config;
+use crate::policy::Policy;
+use crate::prestige::prestige_multiplier;
+use crate::state::{State, XpBreakdown};
+use crate::streak::{streak_multiplier, streak_with};
 use anyhow::Result;
 use bincode::{Decode, Encode};
+use chrono::NaiveDate;
 use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
 
-pub const OUTPUT_SCALE: f64 = 10.0;
-pub const PRECISION_SCALE: f64 = 50.0;
-pub const PEDANTY_SCALE: f64 = 5.0;
-pub const KNOWLEDGE_SCALE: f64 = 500.0;
-
-#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, ValueEnum)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Encode, Decode, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
 pub enum XpType {
     Total,
     Precision,
@@ -17,6 +24,14 @@ pub enum XpType {
     Knowledge,
 }
 
+/// Stats XP is allocated to, in the order they are shown
+pub const SUB_STATS: [XpType; 4] = [
+    XpType::Output,
+    XpType::Pedantry,
+    XpType::Precision,
+    XpType::Knowledge,
+];
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct LevelInfo {
     pub level: u32,
@@ -24,70 +39,266 @@ pub struct LevelInfo {
     pub xp_needed_to_level: u128,
 }
 
-pub fn total_xp_gain(additions: u32, deletions: u32, commit_msg_len: u32) -> Result<u128> {
-    let exp_state = read_xp()?;
-    let precision = calculate_level_info(exp_state.precision, XpType::Precision);
-    let output = calculate_level_info(exp_state.output, XpType::Output);
-    let pedantry = calculate_level_info(exp_state.pedantry, XpType::Pedantry);
+/// Multiplier a sub stat gives at a given level
+pub fn multiplier(level: u32, xp_type: XpType) -> f64 {
+    let scaling = &config().scaling;
+    let scale = match xp_type {
+        XpType::Total => return 1.0,
+        XpType::Precision => scaling.precision,
+        XpType::Output => scaling.output,
+        XpType::Pedantry => scaling.pedantry,
+        XpType::Knowledge => scaling.knowledge,
+    };
+    1.0 + (level as f64 / scale)
+}
+
+/// XP earned by a commit and how it came about
+pub struct XpGain {
+    pub xp: u128,
+    /// XP from each stat before the Knowledge, prestige and streak multipliers
+    pub breakdown: XpBreakdown,
+    /// Caps that reduced the XP
+    pub caps: Vec<Cap>,
+    /// Multipliers applied to the sum of the breakdown
+    pub knowledge: f64,
+    pub prestige: f64,
+    pub streak: f64,
+}
+
+/// XP a commit made on `day` earns on top of the state so far
+pub fn total_xp_gain(
+    additions: u32,
+    deletions: u32,
+    commit_msg_len: u32,
+    day: NaiveDate,
+    state: &State,
+    policy: &Policy,
+) -> XpGain {
+    let exp_state = &state.experience;
+    let earned_today = earned_on(&state.history, day);
     let knowledge = calculate_level_info(exp_state.knowledge, XpType::Knowledge);
+    let knowledge_mult = if policy.is_disabled(XpType::Knowledge) {
+        1.0
+    } else {
+        multiplier(knowledge.level, XpType::Knowledge)
+    };
 
-    let knowledge_mult = 1.0 + (knowledge.level as f64 / KNOWLEDGE_SCALE);
-    let output_mult = additions as f64 * (1.0 + (output.level as f64 / OUTPUT_SCALE));
-    let pedantry_mult = deletions as f64 * (1.0 + (pedantry.level as f64 / PEDANTY_SCALE));
-    let precision_mult =
-        (commit_msg_len / 10) as f64 * (1.0 + (precision.level as f64 / PRECISION_SCALE));
-    let total = (1.0f64.max(output_mult) + 1.0f64.max(pedantry_mult) + 1.0f64.max(precision_mult))
-        * knowledge_mult;
+    let mut caps = Vec::new();
+    // Every enabled stat is worth at least 1 XP, disabled stats are worth nothing
+    let mut stat_xp = |stat: XpType, amount: f64, weight: f64| {
+        if policy.is_disabled(stat) {
+            return 0.0;
+        }
+        let (amount, diminished) = diminish(stat, amount);
+        let level = calculate_level_info(exp_state.of(stat), stat).level;
+        let xp = 1.0f64.max(amount * multiplier(level, stat)) * weight;
+        let (xp, capped) = soft_cap(stat, xp, earned_today.of(stat));
+        if diminished {
+            caps.push(Cap {
+                stat,
+                kind: CapKind::Diminished,
+            });
+        }
+        if capped {
+            caps.push(Cap {
+                stat,
+                kind: CapKind::Daily,
+            });
+        }
+        xp
+    };
+    let breakdown = XpBreakdown {
+        output: stat_xp(XpType::Output, additions as f64, policy.weights.output),
+        pedantry: stat_xp(XpType::Pedantry, deletions as f64, policy.weights.pedantry),
+        precision: stat_xp(
+            XpType::Precision,
+            (commit_msg_len / 10) as f64,
+            policy.weights.precision,
+        ),
+    };
+    let prestige = prestige_multiplier(state.prestige);
+    let streak = streak_multiplier(streak_with(&state.history, day));
+    let total = (breakdown.output + breakdown.pedantry + breakdown.precision)
+        * knowledge_mult
+        * prestige
+        * streak;
 
-    Ok(total as u128)
+    XpGain {
+        xp: total as u128,
+        breakdown,
+        caps,
+        knowledge: knowledge_mult,
+        prestige,
+        streak,
+    }
 }
 
-/// Calculate XP required to reach a specific level from level 0
-/// Uses logistic curve scaling: L / (1 + e^(-k(x - x_0)))
-fn xp_required_for_level(level: u32, xp_type: XpType) -> u128 {
-    // Logistic curve parameters: (L, k, x_0)
-    let (max_xp, steepness, midpoint) = match xp_type {
-        XpType::Total => (1500.0, 0.10, 100.0),
-        XpType::Precision => (1500.0, 0.10, 100.0),
-        XpType::Output => (1500.0, 0.10, 100.0),
-        XpType::Pedantry => (1500.0, 0.10, 100.0),
-        XpType::Knowledge => (1500.0, 0.10, 100.0),
-    };
+/// Shape of the XP required for each level of a stat
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase", deny_unknown_fields)]
+pub enum Curve {
+    /// The original levels, a fixed logistic curve that drops back to linear growth past the midpoint
+    Legacy {},
+    /// Logistic curve L / (1 + e^(-k(x - x_0))) up to the midpoint, linear afterwards
+    Logistic {
+        max_xp: f64,
+        steepness: f64,
+        midpoint: f64,
+    },
+    /// base * growth^(level - 1)
+    Exponential { base: f64, growth: f64 },
+    /// base + coefficient * level^exponent
+    Polynomial {
+        base: f64,
+        coefficient: f64,
+        exponent: f64,
+    },
+    /// XP for each level in order, the last entry repeats for higher levels
+    Table { xp: Vec<u64> },
+}
 
-    if level == 0 {
-        return 0;
+impl Default for Curve {
+    fn default() -> Self {
+        Curve::Legacy {}
     }
+}
 
-    let level_f = level as f64;
+/// Settings of the legacy curve, fixed since it exists to keep the original levels
+const LEGACY_MAX_XP: f64 = 1500.0;
+const LEGACY_STEEPNESS: f64 = 0.10;
+const LEGACY_MIDPOINT: f64 = 100.0;
 
-    if level_f <= midpoint {
+/// Logistic curve up to the midpoint and linear past it, with the level bonus past it only if `bonus`
+fn logistic(level: f64, max_xp: f64, steepness: f64, midpoint: f64, bonus: bool) -> f64 {
+    if level <= midpoint {
         // For levels up to midpoint, use logistic curve: f(x) = L / (1 + e^(-k(x - x_0)))
-        let exponent = -steepness * (level_f - midpoint);
-        let xp = max_xp / (1.0 + exponent.exp());
-        xp.round() as u128 + 2u128 * level as u128
+        let exponent = -steepness * (level - midpoint);
+        (max_xp / (1.0 + exponent.exp())).round() + 2.0 * level
     } else {
-        // For levels beyond midpoint, use linear growth
-        // Calculate XP at midpoint using logistic curve
-        let exponent_at_midpoint = -steepness * 0.0; // This is 0
-        let xp_at_midpoint = max_xp / (1.0 + exponent_at_midpoint.exp()); // This is max_xp / 2
-
-        // Calculate slope as derivative of logistic curve at midpoint: L * k / 4
+        // For levels beyond midpoint, grow linearly from the midpoint (L / 2)
+        // with the logistic curve's slope there: L * k / 4
         let slope = max_xp * steepness / 4.0;
+        let linear = (max_xp / 2.0 + slope * (level - midpoint)).round();
+        if bonus { linear + 2.0 * level } else { linear }
+    }
+}
 
-        // Linear growth from midpoint
-        let xp = xp_at_midpoint + slope * (level_f - midpoint);
-        xp.round() as u128
+impl Curve {
+    /// Levels checked when validating a curve
+    const VALIDATED_LEVELS: u32 = 1000;
+
+    /// XP required to go from the previous level to this one
+    pub fn xp_required(&self, level: u32) -> u128 {
+        if level == 0 {
+            return 0;
+        }
+        let level_f = level as f64;
+        let xp = match self {
+            // The original levels left out the level bonus past the midpoint
+            Curve::Legacy {} => logistic(
+                level_f,
+                LEGACY_MAX_XP,
+                LEGACY_STEEPNESS,
+                LEGACY_MIDPOINT,
+                false,
+            ),
+            Curve::Logistic {
+                max_xp,
+                steepness,
+                midpoint,
+            } => logistic(level_f, *max_xp, *steepness, *midpoint, true),
+            Curve::Exponential { base, growth } => base * growth.powf(level_f - 1.0),
+            Curve::Polynomial {
+                base,
+                coefficient,
+                exponent,
+            } => base + coefficient * level_f.powf(*exponent),
+            Curve::Table { xp } => {
+                let idx = (level as usize - 1).min(xp.len().saturating_sub(1));
+                xp.get(idx).copied().unwrap_or(0) as f64
+            }
+        };
+        // Keep cumulative sums far away from overflowing
+        xp.round().min(u64::MAX as f64) as u128
     }
+
+    /// Every level must cost XP and, except on the legacy curve, at least as much as the level before it
+    pub fn validate(&self) -> Result<(), String> {
+        if let Curve::Table { xp } = self
+            && xp.is_empty()
+        {
+            return Err("table must list the XP of at least one level".to_string());
+        }
+        let mut previous = 0;
+        for level in 1..=Self::VALIDATED_LEVELS {
+            let xp = self.xp_required(level);
+            if xp == 0 {
+                return Err(format!("level {level} requires no XP"));
+            }
+            if xp < previous && !matches!(self, Curve::Legacy {}) {
+                return Err(format!(
+                    "curve is not monotonic, level {level} requires {xp} XP but level {} requires {previous}",
+                    level - 1
+                ));
+            }
+            previous = xp;
+        }
+        Ok(())
+    }
+}
+
+/// Level curve of every stat
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Curves {
+    pub total: Curve,
+    pub precision: Curve,
+    pub output: Curve,
+    pub pedantry: Curve,
+    pub knowledge: Curve,
+}
+
+impl Curves {
+    pub fn get(&self, xp_type: XpType) -> &Curve {
+        match xp_type {
+            XpType::Total => &self.total,
+            XpType::Precision => &self.precision,
+            XpType::Output => &self.output,
+            XpType::Pedantry => &self.pedantry,
+            XpType::Knowledge => &self.knowledge,
+        }
+    }
+}
+
+/// Calculate XP required to reach a specific level from the previous one
+fn xp_required_for_level(level: u32, xp_type: XpType) -> u128 {
+    config().curves.get(xp_type).xp_required(level)
+}
+
+/// Cumulative XP needed to reach each level of each stat, grown as higher levels are reached
+static THRESHOLDS: LazyLock<Mutex<HashMap<XpType, Vec<u128>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Run `f` on the thresholds of a stat once they reach past `done`
+fn with_thresholds<R>(
+    xp_type: XpType,
+    done: impl Fn(&[u128]) -> bool,
+    f: impl FnOnce(&[u128]) -> R,
+) -> R {
+    let mut cache = THRESHOLDS.lock().expect("Level thresholds lock poisoned");
+    let thresholds = cache.entry(xp_type).or_insert_with(|| vec![0]);
+    while !done(thresholds) {
+        let level = thresholds.len() as u32;
+        let total = thresholds[thresholds.len() - 1] + xp_required_for_level(level, xp_type);
+        thresholds.push(total);
+    }
+    f(thresholds)
 }
 
 /// Calculate total XP required to reach a specific level
 /// This is the cumulative sum of XP required for each level
 fn total_xp_for_level(level: u32, xp_type: XpType) -> u128 {
-    if level == 0 {
-        return 0;
-    }
-
-    (1..=level).map(|l| xp_required_for_level(l, xp_type)).sum()
+    with_thresholds(xp_type, |t| t.len() > level as usize, |t| t[level as usize])
 }
 
 /// Given current XP, calculate level and XP needed for next level
@@ -96,31 +307,26 @@ pub fn calculate_level_info(current_xp: u128, xp_type: XpType) -> LevelInfo {
         return LevelInfo {
             level: 0,
             current_level_progress: 0,
-            xp_needed_to_level: 50,
+            xp_needed_to_level: xp_required_for_level(1, xp_type),
         };
     }
 
-    // Find the current level by checking total XP thresholds
-    let mut level = 1;
-    while total_xp_for_level(level, xp_type) <= current_xp {
-        level += 1;
-    }
-    level -= 1; // Back up to the level we actually achieved
-
-    let total_xp_current_level = total_xp_for_level(level, xp_type);
-    let total_xp_for_next_level = total_xp_for_level(level + 1, xp_type);
-    let xp_needed_to_level = total_xp_for_next_level - total_xp_current_level;
-    let current_level_progress = current_xp - total_xp_current_level;
-
-    LevelInfo {
-        level,
-        current_level_progress,
-        xp_needed_to_level,
-    }
+    // Binary search for the highest level whose threshold has been reached
+    with_thresholds(
+        xp_type,
+        |t| t[t.len() - 1] > current_xp,
+        |t| {
+            let level = t.partition_point(|&total| total <= current_xp) - 1;
+            LevelInfo {
+                level: level as u32,
+                current_level_progress: current_xp - t[level],
+                xp_needed_to_level: t[level + 1] - t[level],
+            }
+        },
+    )
 }
 
 /// Get XP requirements for multiple levels (useful for displaying progression)
-#[allow(dead_code)]
 pub fn get_level_progression(max_level: u32, xp_type: XpType) -> Vec<(u32, u128, u128)> {
     (1..=max_level)
         .map(|level| {
@@ -1,6 +1,8 @@
 use crate::ascii::{BANG, BANNER, display_side_by_side};
+use crate::config::config;
 use crate::git::{GitRepo, git_username};
-use crate::state::add_repo;
+use crate::state::{add_repo, lock_state};
+use crate::theme::bold;
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use std::fs::{self, File, Permissions};
@@ -13,10 +15,10 @@ static PROJECT_DIRS: LazyLock<ProjectDirs> = LazyLock::new(|| {
     ProjectDirs::from("io", "m51", "git-ascend").expect("Could not determine $HOME location")
 });
 
-pub fn setup(repo_path: &str) -> Result<()> {
+pub fn setup(repo_path: &str, background: bool) -> Result<()> {
     println!("Setting up repository at {repo_path}");
     create_data_directory()?;
-    create_post_commit_hook(repo_path)?;
+    create_post_commit_hook(repo_path, background)?;
     register_repository(repo_path)?;
     println!("Setup complete! Make a commit or run 'git ascend help' for more options.");
     Ok(())
@@ -37,6 +39,10 @@ pub fn check_setup(repo_path: &str) -> bool {
 }
 
 pub fn data_location() -> String {
+    let configured = &config().data_dir;
+    if !configured.is_empty() {
+        return configured.clone();
+    }
     let data_dir = PROJECT_DIRS.data_dir();
     data_dir.to_string_lossy().into_owned()
 }
@@ -45,10 +51,10 @@ fn register_repository(repo_path: &str) -> Result<()> {
     let repo = GitRepo::new(repo_path)?;
     let first_commit = repo.first_commit_hash()?;
     let last_commit = repo.head_commit_hash()?;
-    add_repo(first_commit, last_commit)?;
+    add_repo(&lock_state()?, first_commit, last_commit, repo.workdir()?)?;
     Ok(())
 }
-fn create_post_commit_hook(repo_path: &str) -> Result<()> {
+fn create_post_commit_hook(repo_path: &str, background: bool) -> Result<()> {
     let post_commit = format!("{repo_path}/.git/hooks/post-commit");
     let post_commit_path = Path::new(&post_commit);
 
@@ -58,7 +64,12 @@ fn create_post_commit_hook(repo_path: &str) -> Result<()> {
             "Post-commit hook already exists. Manually add 'git ascend' to the hook if it's not there already."
         );
     } else {
-        let hook_content = "#!/bin/sh\n# Git Ascend post-commit hook\ngit ascend\n";
+        let command = if background {
+            "git ascend --background"
+        } else {
+            "git ascend"
+        };
+        let hook_content = format!("#!/bin/sh\n# Git Ascend post-commit hook\n{command}\n");
         let mut file = File::create(post_commit_path)
             .context("Error creating post-commit hook. Is this a git repository?")?;
         file.write_all(hook_content.as_bytes())?;
@@ -93,5 +104,5 @@ begin to find out, for today is the first day of your"
     println!("{banner}");
     println!("Welcome to the beginning of your ascension, {username}!");
     println!();
-    println!("Run \x1b[1mgit ascend setup\x1b[0m to begin your journey.");
+    println!("Run {} to begin your journey.", bold("git ascend setup"));
 }
@@ -1,18 +1,48 @@
 use anyhow::anyhow;
-use std::{collections::HashMap, fs::File, path::Path};
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions, TryLockError},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
 
-use crate::{scaling::XpType, setup::data_location};
+use crate::{
+    allocation::Allocation, caps::Cap, prompt::write_summary, scaling::XpType, setup::data_location,
+};
 use anyhow::{Context, Result};
 use bincode::{Decode, Encode, config};
+use chrono::{DateTime, Local, NaiveDate};
+use serde::Serialize;
 
+/// Starts every versioned state file, no state written before versioning can start with 0xff
+const STATE_MAGIC: &[u8] = b"\xffgit-ascend";
+/// Layout of `State`, raise it and convert older layouts whenever a field changes
+const STATE_VERSION: u32 = 1;
+
+/// Everything kept between runs, loaded whole by commands that change several parts of it at once
 #[derive(Encode, Decode, Debug)]
-struct State {
-    experience: Experience,
-    current_stat: XpType,
-    repos: HashMap<String, RepoState>,
+pub struct State {
+    pub experience: Experience,
+    /// Stats new XP goes into
+    pub allocation: Allocation,
+    pub repos: HashMap<String, RepoState>,
+    pub pending: Option<PendingAward>,
+    /// Times levels were traded for a permanent multiplier, kept across resets
+    pub prestige: u32,
+    /// Every commit XP was awarded for, oldest first
+    pub history: Vec<CommitRecord>,
+    pub achievements: Vec<UnlockedAchievement>,
+    /// Keys of quests already rewarded
+    pub completed_quests: Vec<String>,
+    /// XP paid out for completed quests, oldest first
+    pub quest_rewards: Vec<QuestReward>,
+    /// XP moved between stats, oldest first
+    pub respecs: Vec<Respec>,
 }
 
-#[derive(Encode, Decode, Debug)]
+#[derive(Encode, Decode, Debug, Clone, Default)]
 pub struct Experience {
     pub total: u128,
     pub precision: u128,
@@ -21,39 +51,253 @@ pub struct Experience {
     pub knowledge: u128,
 }
 
+impl Experience {
+    pub fn of(&self, stat: XpType) -> u128 {
+        match stat {
+            XpType::Total => self.total,
+            XpType::Precision => self.precision,
+            XpType::Output => self.output,
+            XpType::Pedantry => self.pedantry,
+            XpType::Knowledge => self.knowledge,
+        }
+    }
+
+    fn of_mut(&mut self, stat: XpType) -> &mut u128 {
+        match stat {
+            XpType::Total => &mut self.total,
+            XpType::Precision => &mut self.precision,
+            XpType::Output => &mut self.output,
+            XpType::Pedantry => &mut self.pedantry,
+            XpType::Knowledge => &mut self.knowledge,
+        }
+    }
+
+    /// XP of every stat in either, stopping at the largest amount
+    pub fn saturating_add(&self, other: &Experience) -> Experience {
+        self.zip(other, u128::saturating_add)
+    }
+
+    /// XP of every stat in this but not the other, stopping at 0
+    pub fn saturating_sub(&self, other: &Experience) -> Experience {
+        self.zip(other, u128::saturating_sub)
+    }
+
+    fn zip(&self, other: &Experience, f: fn(u128, u128) -> u128) -> Experience {
+        Experience {
+            total: f(self.total, other.total),
+            precision: f(self.precision, other.precision),
+            output: f(self.output, other.output),
+            pedantry: f(self.pedantry, other.pedantry),
+            knowledge: f(self.knowledge, other.knowledge),
+        }
+    }
+}
+
 #[derive(Encode, Decode, Debug, Clone)]
 pub struct RepoState {
     pub last_recorded_commit: String,
+    /// Working directory the repository was last seen at
+    pub path: Option<String>,
+}
+
+/// A commit XP was awarded for
+#[derive(Encode, Decode, Debug, Clone)]
+pub struct CommitRecord {
+    pub sha: String,
+    pub repo_id: String,
+    /// Commit time in seconds since the epoch
+    pub timestamp: i64,
+    /// Offset of the committer's time zone from UTC
+    pub offset_minutes: i32,
+    pub lines_added: u32,
+    pub lines_deleted: u32,
+    pub message_len: u32,
+    pub has_body: bool,
+    pub touched_tests: bool,
+    pub resolved_conflicts: bool,
+    pub xp: u128,
+    pub breakdown: XpBreakdown,
+    /// How the XP was split between stats
+    pub allocation: Allocation,
+}
+
+/// XP each stat contributed to a commit before the Knowledge, prestige and streak multipliers
+#[derive(Encode, Decode, Debug, Clone, Default, Serialize)]
+pub struct XpBreakdown {
+    pub output: f64,
+    pub pedantry: f64,
+    pub precision: f64,
+}
+
+impl XpBreakdown {
+    pub fn of(&self, stat: XpType) -> f64 {
+        match stat {
+            XpType::Output => self.output,
+            XpType::Pedantry => self.pedantry,
+            XpType::Precision => self.precision,
+            XpType::Total | XpType::Knowledge => 0.0,
+        }
+    }
+}
+
+impl CommitRecord {
+    /// Calendar day of the commit in the committer's own time zone
+    pub fn local_date(&self) -> NaiveDate {
+        let local_seconds = self.timestamp + self.offset_minutes as i64 * 60;
+        DateTime::from_timestamp(local_seconds, 0)
+            .unwrap_or_default()
+            .date_naive()
+    }
+}
+
+#[derive(Encode, Decode, Debug, Clone)]
+pub struct UnlockedAchievement {
+    pub id: String,
+    /// When the achievement was unlocked, in seconds since the epoch
+    pub unlocked_at: i64,
+}
+
+impl UnlockedAchievement {
+    /// Calendar day the achievement was unlocked on in the local time zone
+    pub fn local_date(&self) -> NaiveDate {
+        DateTime::from_timestamp(self.unlocked_at, 0)
+            .unwrap_or_default()
+            .with_timezone(&Local)
+            .date_naive()
+    }
+}
+
+/// XP paid out for a completed quest
+#[derive(Encode, Decode, Debug, Clone)]
+pub struct QuestReward {
+    /// When the quest was completed, in seconds since the epoch
+    pub timestamp: i64,
+    pub key: String,
+    pub xp: u128,
+    /// How the XP was split between stats
+    pub allocation: Allocation,
+}
+
+impl QuestReward {
+    /// Calendar day the quest was completed on in the local time zone
+    pub fn local_date(&self) -> NaiveDate {
+        DateTime::from_timestamp(self.timestamp, 0)
+            .unwrap_or_default()
+            .with_timezone(&Local)
+            .date_naive()
+    }
+}
+
+/// XP moved from one stat to another
+#[derive(Encode, Decode, Debug, Clone)]
+pub struct Respec {
+    /// When the XP was moved, in seconds since the epoch
+    pub timestamp: i64,
+    pub from: XpType,
+    pub to: XpType,
+    /// XP taken from `from`
+    pub amount: u128,
+    /// Part of `amount` that was lost instead of given to `to`
+    pub tax: u128,
+}
+
+/// XP collected in the background that has not been shown to the user yet
+#[derive(Encode, Decode, Debug, Clone)]
+pub struct PendingAward {
+    pub commits: u32,
+    /// XP each stat gained, shown ending at the current XP since other commands may change it meanwhile
+    pub gained: Experience,
+    /// Ids of achievements unlocked along the way
+    pub achievements: Vec<String>,
+    /// Keys of quests completed along the way
+    pub quests: Vec<String>,
+    /// Caps that reduced the XP along the way
+    pub caps: Vec<Cap>,
+}
+
+/// Exclusive lock on the state, needed to change it and released when dropped or the process exits
+pub struct StateLock {
+    _file: File,
+}
+
+/// State written before state files were versioned
+#[derive(Decode)]
+struct BaselineState {
+    experience: Experience,
+    current_stat: XpType,
+    repos: HashMap<String, BaselineRepoState>,
+}
+
+#[derive(Decode)]
+struct BaselineRepoState {
+    last_recorded_commit: String,
+}
+
+impl From<BaselineState> for State {
+    fn from(baseline: BaselineState) -> Self {
+        // Leveling Total only ever added to Total, Knowledge is what new users start with
+        let stat = match baseline.current_stat {
+            XpType::Total => XpType::Knowledge,
+            stat => stat,
+        };
+        State {
+            experience: baseline.experience,
+            allocation: Allocation::single(stat),
+            repos: baseline
+                .repos
+                .into_iter()
+                .map(|(id, repo)| {
+                    let repo = RepoState {
+                        last_recorded_commit: repo.last_recorded_commit,
+                        path: None,
+                    };
+                    (id, repo)
+                })
+                .collect(),
+            ..State::new()
+        }
+    }
 }
 
 impl State {
     fn new() -> Self {
         State {
-            experience: Experience {
-                total: 0,
-                precision: 0,
-                output: 0,
-                pedantry: 0,
-                knowledge: 0,
-            },
-            current_stat: XpType::Knowledge,
+            experience: Experience::default(),
+            allocation: Allocation::single(XpType::Knowledge),
             repos: HashMap::new(),
+            pending: None,
+            prestige: 0,
+            history: Vec::new(),
+            achievements: Vec::new(),
+            completed_quests: Vec::new(),
+            quest_rewards: Vec::new(),
+            respecs: Vec::new(),
         }
     }
-}
 
-pub fn inc_xp(amt: u128) -> Result<Experience> {
-    let mut state = read_state()?;
-    state.experience.total += amt;
-    match state.current_stat {
-        XpType::Total => {}
-        XpType::Knowledge => state.experience.knowledge += amt,
-        XpType::Precision => state.experience.precision += amt,
-        XpType::Output => state.experience.output += amt,
-        XpType::Pedantry => state.experience.pedantry += amt,
-    }
-    write_state(&state)?;
-    Ok(state.experience)
+    /// Read the state to change it in memory, nothing is kept until it is saved
+    pub fn load(_lock: &StateLock) -> Result<State> {
+        read_state()
+    }
+
+    pub fn save(&self, lock: &StateLock) -> Result<()> {
+        write_state(lock, self)
+    }
+
+    /// Add XP to Total and split it between the stats being leveled
+    pub fn add_xp(&mut self, amt: u128) {
+        self.experience.total = self.experience.total.saturating_add(amt);
+        for (stat, xp) in self.allocation.split(amt) {
+            let stat_xp = self.experience.of_mut(stat);
+            *stat_xp = stat_xp.saturating_add(xp);
+        }
+    }
+
+    pub fn repo_mut(&mut self, repo_id: &str) -> Result<&mut RepoState> {
+        self.repos
+            .get_mut(repo_id)
+            .ok_or_else(|| anyhow!("Repository not found in state. Was setup successful?"))
+    }
 }
 
 pub fn read_xp() -> Result<Experience> {
@@ -61,79 +305,289 @@ pub fn read_xp() -> Result<Experience> {
     Ok(state.experience)
 }
 
-pub fn inc_last_commit(repo_id: &str, new_commit: &str) -> Result<()> {
-    let mut state = read_state()?;
-    if let Some(repo) = state.repos.get_mut(repo_id) {
-        repo.last_recorded_commit = new_commit.to_string();
-    }
-    write_state(&state)?;
-    Ok(())
-}
-
-pub fn add_repo(repo_id: String, last_recorded_commit: String) -> Result<()> {
+pub fn add_repo(
+    lock: &StateLock,
+    repo_id: String,
+    last_recorded_commit: String,
+    path: String,
+) -> Result<()> {
     let mut state = read_state()?;
     state.repos.insert(
         repo_id,
         RepoState {
             last_recorded_commit,
+            path: Some(path),
         },
     );
-    write_state(&state)
+    write_state(lock, &state)
 }
 
-pub fn repo_state(repo_id: &str) -> Result<RepoState> {
+/// All registered repositories, keyed by repository id
+pub fn repos() -> Result<HashMap<String, RepoState>> {
     let state = read_state()?;
-    let r_state = state.repos.get(repo_id);
-    if let Some(repo) = r_state {
-        Ok(repo.clone())
-    } else {
-        Err(anyhow!(
-            "Repository not found in state. Was setup successful?"
-        ))
+    Ok(state.repos)
+}
+
+pub fn repo_state(repo_id: &str) -> Result<RepoState> {
+    read_state()?.repo_mut(repo_id).cloned()
+}
+
+pub fn add_pending(lock: &StateLock, award: PendingAward) -> Result<()> {
+    let mut state = read_state()?;
+    state.pending = Some(match state.pending {
+        Some(mut pending) => {
+            pending.commits += award.commits;
+            pending.gained = pending.gained.saturating_add(&award.gained);
+            pending.achievements.extend(award.achievements);
+            pending.quests.extend(award.quests);
+            for cap in award.caps {
+                if !pending.caps.contains(&cap) {
+                    pending.caps.push(cap);
+                }
+            }
+            pending
+        }
+        None => award,
+    });
+    write_state(lock, &state)
+}
+
+pub fn take_pending(lock: &StateLock) -> Result<Option<PendingAward>> {
+    let mut state = read_state()?;
+    let pending = state.pending.take();
+    if pending.is_some() {
+        write_state(lock, &state)?;
+    }
+    Ok(pending)
+}
+
+/// Wait until no other git-ascend process is modifying the state
+pub fn lock_state() -> Result<StateLock> {
+    const GIVE_UP_AFTER: Duration = Duration::from_secs(30);
+
+    let path = Path::new(&data_location()).join("state.lock");
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&path)
+        .context("Could not open state lock")?;
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(StateLock { _file: file }),
+            Err(TryLockError::WouldBlock) if started.elapsed() < GIVE_UP_AFTER => {
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(TryLockError::WouldBlock) => {
+                return Err(anyhow!("Timed out waiting for lock {}", path.display()));
+            }
+            Err(TryLockError::Error(e)) => return Err(e).context("Could not lock state"),
+        }
     }
 }
 
-pub fn reset_xp() -> Result<()> {
+pub fn read_history() -> Result<Vec<CommitRecord>> {
+    let state = read_state()?;
+    Ok(state.history)
+}
+
+pub fn read_achievements() -> Result<Vec<UnlockedAchievement>> {
+    let state = read_state()?;
+    Ok(state.achievements)
+}
+
+pub fn read_quest_rewards() -> Result<Vec<QuestReward>> {
+    let state = read_state()?;
+    Ok(state.quest_rewards)
+}
+
+pub fn read_completed_quests() -> Result<Vec<String>> {
+    let state = read_state()?;
+    Ok(state.completed_quests)
+}
+
+/// Apply and record a move of XP between stats, the tax is lost from Total too
+pub fn add_respec(lock: &StateLock, respec: Respec) -> Result<()> {
     let mut state = read_state()?;
-    state.experience = Experience {
-        total: 0,
-        precision: 0,
-        output: 0,
-        pedantry: 0,
-        knowledge: 0,
-    };
-    write_state(&state)?;
+    // Nothing is written if any step fails, so the state is never left half moved
+    let experience = &mut state.experience;
+    let from = experience.of_mut(respec.from);
+    *from = from.checked_sub(respec.amount).ok_or_else(|| {
+        anyhow!(
+            "{:?} has less than {} XP to move",
+            respec.from,
+            respec.amount
+        )
+    })?;
+    let moved = respec
+        .amount
+        .checked_sub(respec.tax)
+        .ok_or_else(|| anyhow!("Respec tax is larger than the XP moved"))?;
+    let to = experience.of_mut(respec.to);
+    *to = to
+        .checked_add(moved)
+        .ok_or_else(|| anyhow!("{:?} cannot hold {moved} more XP", respec.to))?;
+    experience.total = experience
+        .total
+        .checked_sub(respec.tax)
+        .ok_or_else(|| anyhow!("Total has less than {} XP to pay the tax", respec.tax))?;
+    state.respecs.push(respec);
+    write_state(lock, &state)
+}
+
+pub fn read_respecs() -> Result<Vec<Respec>> {
+    let state = read_state()?;
+    Ok(state.respecs)
+}
+
+pub fn read_prestige() -> Result<u32> {
+    let state = read_state()?;
+    Ok(state.prestige)
+}
+
+/// Reset every level in exchange for another prestige, returning the new count
+pub fn inc_prestige(lock: &StateLock) -> Result<u32> {
+    let mut state = read_state()?;
+    state.experience = Experience::default();
+    // XP collected in the background was reset along with everything else
+    state.pending = None;
+    state.prestige += 1;
+    write_state(lock, &state)?;
+    Ok(state.prestige)
+}
+
+pub fn reset_xp(lock: &StateLock) -> Result<()> {
+    let mut state = read_state()?;
+    state.experience = Experience::default();
+    state.pending = None;
+    write_state(lock, &state)?;
     Ok(())
 }
 
-fn write_state(state: &State) -> Result<()> {
-    let save_path = Path::new(&data_location()).join("state.bin");
-    let mut file = File::create(save_path).context("Could not create state file")?;
-    bincode::encode_into_std_write(state, &mut file, config::standard())?;
+pub fn state_path() -> PathBuf {
+    Path::new(&data_location()).join("state.bin")
+}
+
+/// Make sure the state file can be decoded without creating it if it is missing
+pub fn check_state() -> Result<()> {
+    let bytes = fs::read(state_path()).context("Could not open state file")?;
+    decode_state(&bytes)?;
     Ok(())
 }
 
-fn read_state() -> Result<State> {
-    let save_path = Path::new(&data_location()).join("state.bin");
-    if !save_path.exists() {
-        write_state(&State::new())?;
+fn encode_state(state: &State) -> Result<Vec<u8>> {
+    let mut bytes = STATE_MAGIC.to_vec();
+    bytes.extend(bincode::encode_to_vec(STATE_VERSION, config::standard())?);
+    bytes.extend(bincode::encode_to_vec(state, config::standard())?);
+    Ok(bytes)
+}
+
+/// Decode a state file of any version, converting older layouts to the current one
+fn decode_state(bytes: &[u8]) -> Result<State> {
+    let Some(versioned) = bytes.strip_prefix(STATE_MAGIC) else {
+        let baseline: BaselineState = decode_all(bytes)?;
+        return Ok(baseline.into());
+    };
+    let (version, read): (u32, usize) = bincode::decode_from_slice(versioned, config::standard())
+        .context("Could not decode state file version")?;
+    match version {
+        STATE_VERSION => decode_all(&versioned[read..]),
+        _ => Err(anyhow!(
+            "State file version {version} is not supported, it was written by a newer git-ascend"
+        )),
     }
-    let mut file = File::open(save_path).context("Could not open state file")?;
-    let state = bincode::decode_from_std_read(&mut file, config::standard())?;
-    Ok(state)
 }
 
-pub fn set_current_stat(stat: XpType) -> Result<()> {
-    if stat == XpType::Total {
-        return Err(anyhow!("Cannot set current stat to Total"));
+/// Decode a value that must take up all of the bytes
+fn decode_all<T: Decode<()>>(bytes: &[u8]) -> Result<T> {
+    let (value, read) = bincode::decode_from_slice(bytes, config::standard())
+        .context("Could not decode state file")?;
+    if read != bytes.len() {
+        return Err(anyhow!(
+            "Could not decode state file: unexpected trailing data"
+        ));
     }
+    Ok(value)
+}
+
+fn write_state(_lock: &StateLock, state: &State) -> Result<()> {
+    write_atomic(&state_path(), &encode_state(state)?).context("Could not write state file")?;
+    write_summary(&state.experience, &state.allocation, &state.history)
+}
+
+/// Rebuild the summary `git ascend prompt` reads from the state
+pub fn refresh_summary(_lock: &StateLock) -> Result<()> {
+    let state = read_state()?;
+    write_summary(&state.experience, &state.allocation, &state.history)
+}
+
+fn read_state() -> Result<State> {
+    let bytes = match fs::read(state_path()) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(State::new()),
+        Err(e) => return Err(e).context("Could not open state file"),
+    };
+    decode_state(&bytes)
+}
+
+/// Replace a file in one step, so readers that do not take the lock never see it half written
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, contents)?;
+    fs::rename(tmp, path)
+}
+
+pub fn set_allocation(lock: &StateLock, allocation: Allocation) -> Result<()> {
     let mut state = read_state()?;
-    state.current_stat = stat;
-    write_state(&state)?;
+    state.allocation = allocation;
+    write_state(lock, &state)?;
     Ok(())
 }
 
-pub fn read_current_stat() -> Result<XpType> {
+pub fn read_allocation() -> Result<Allocation> {
     let state = read_state()?;
-    Ok(state.current_stat)
+    Ok(state.allocation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_baseline_state() {
+        let state = decode_state(include_bytes!("../tests/fixtures/baseline-state.bin")).unwrap();
+        assert_eq!(state.experience.total, 594);
+        assert_eq!(state.experience.output, 304);
+        assert_eq!(state.experience.pedantry, 290);
+        assert_eq!(state.experience.precision, 0);
+        assert_eq!(state.allocation, Allocation::single(XpType::Pedantry));
+        let repo = &state.repos["eea94310a29f9980c3d4f259e64b64db8a6761ca"];
+        assert_eq!(
+            repo.last_recorded_commit,
+            "b3d12ebc008f33a8984d241c0b2e0932f4b652bf"
+        );
+        assert_eq!(repo.path, None);
+        assert!(state.history.is_empty());
+        assert_eq!(state.prestige, 0);
+    }
+
+    #[test]
+    fn round_trips_versioned_state() {
+        let mut state = State::new();
+        state.experience.total = 71;
+        state.prestige = 2;
+        let bytes = encode_state(&state).unwrap();
+        assert!(bytes.starts_with(STATE_MAGIC));
+        let decoded = decode_state(&bytes).unwrap();
+        assert_eq!(decoded.experience.total, 71);
+        assert_eq!(decoded.prestige, 2);
+    }
+
+    #[test]
+    fn rejects_newer_versions() {
+        let mut bytes = STATE_MAGIC.to_vec();
+        bytes.extend(bincode::encode_to_vec(STATE_VERSION + 1, config::standard()).unwrap());
+        assert!(decode_state(&bytes).is_err());
+    }
 }
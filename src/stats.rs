@@ -1,10 +1,15 @@
 use crate::ascii::display_number_x;
-use crate::progress::{format_progress_bar, short_bar_outside_label};
-use crate::scaling::{
-    KNOWLEDGE_SCALE, OUTPUT_SCALE, PEDANTY_SCALE, PRECISION_SCALE, XpType, calculate_level_info,
-};
-use crate::state::{read_current_stat, read_xp};
+use crate::config::config;
+use crate::output::{LevelReport, SCHEMA_VERSION, ShareReport, allocation_report};
+use crate::prestige::{next_threshold, prestige_multiplier};
+use crate::progress::{bar_width, format_progress_bar, short_bar_outside_label};
+use crate::scaling::{XpType, calculate_level_info, multiplier};
+use crate::state::{read_allocation, read_history, read_prestige, read_xp};
+use crate::streak::{streak, streak_multiplier};
+use crate::theme::{bold, pad};
 use anyhow::Result;
+use chrono::Local;
+use serde::Serialize;
 
 pub fn main_stats() -> Result<()> {
     let xp = read_xp()?;
@@ -12,10 +17,28 @@ pub fn main_stats() -> Result<()> {
     let progress_bar = format_progress_bar(
         level_info.current_level_progress,
         level_info.xp_needed_to_level,
-        Some(50),
         None,
+        None,
+        Some(XpType::Total),
+    );
+    let prestige = read_prestige()?;
+    println!(
+        "{}{}\n",
+        display_number_x(level_info.level, prestige),
+        progress_bar,
     );
-    println!("{}{}\n", display_number_x(level_info.level), progress_bar,);
+    if prestige > 0 {
+        println!(
+            "Prestige {prestige}: all XP multiplied by {:.2}x\n",
+            prestige_multiplier(prestige)
+        );
+    }
+    if level_info.level >= next_threshold(prestige) {
+        println!(
+            "You can prestige! Run {} to trade your levels for a permanent multiplier.\n",
+            bold("git ascend prestige")
+        );
+    }
     Ok(())
 }
 
@@ -25,60 +48,141 @@ pub fn xp_levels() -> Result<()> {
     let output = calculate_level_info(xp.output, XpType::Output);
     let pedantry = calculate_level_info(xp.pedantry, XpType::Pedantry);
     let knowledge = calculate_level_info(xp.knowledge, XpType::Knowledge);
-    let current_stat = read_current_stat()?;
+    let allocation = read_allocation()?;
     let mut result = String::new();
+    // Bar plus its level and percentage, so the multipliers line up
+    let padded = bar_width(Some(config().progress.short_width)) as usize + 18;
 
     let output_bar = short_bar_outside_label(
         output.current_level_progress,
         output.xp_needed_to_level,
         &output.level.to_string(),
+        XpType::Output,
     );
     result.push_str(&format!(
-        "{:<10} {:<43} {:.2}x\n",
+        "{:<10} {} {:.2}x\n",
         "Output",
-        output_bar,
-        (1.0 + (output.level as f64 / OUTPUT_SCALE)),
+        pad(&output_bar, padded),
+        multiplier(output.level, XpType::Output),
     ));
 
     let pedantry_bar = short_bar_outside_label(
         pedantry.current_level_progress,
         pedantry.xp_needed_to_level,
         &pedantry.level.to_string(),
+        XpType::Pedantry,
     );
     result.push_str(&format!(
-        "{:<10} {:<43} {:.2}x\n",
+        "{:<10} {} {:.2}x\n",
         "Pedantry",
-        pedantry_bar,
-        (1.0 + (pedantry.level as f64 / PEDANTY_SCALE)),
+        pad(&pedantry_bar, padded),
+        multiplier(pedantry.level, XpType::Pedantry),
     ));
 
     let precision_bar = short_bar_outside_label(
         precision.current_level_progress,
         precision.xp_needed_to_level,
         &precision.level.to_string(),
+        XpType::Precision,
     );
     result.push_str(&format!(
-        "{:<10} {:<43} {:.2}x\n",
+        "{:<10} {} {:.2}x\n",
         "Precision",
-        precision_bar,
-        (1.0 + (precision.level as f64 / PRECISION_SCALE)),
+        pad(&precision_bar, padded),
+        multiplier(precision.level, XpType::Precision),
     ));
 
     let knowledge_bar = short_bar_outside_label(
         knowledge.current_level_progress,
         knowledge.xp_needed_to_level,
         &knowledge.level.to_string(),
+        XpType::Knowledge,
     );
     result.push_str(&format!(
-        "{:<10} {:<43} {:.2}x\n",
+        "{:<10} {} {:.2}x\n",
         "Knowledge",
-        knowledge_bar,
-        (1.0 + (knowledge.level as f64 / KNOWLEDGE_SCALE)),
+        pad(&knowledge_bar, padded),
+        multiplier(knowledge.level, XpType::Knowledge),
     ));
     println!("{result}");
-    println!("Active Stat: \x1b[1m{current_stat:?}\x1b[0m");
+    println!("Active Stat: {}", bold(&allocation.to_string()));
+    let streak = streak(&read_history()?, Local::now().date_naive());
+    println!(
+        "Streak: {} days ({:.2}x), longest {} days",
+        bold(&streak.current.to_string()),
+        streak_multiplier(streak.current),
+        streak.longest
+    );
     println!(
-        "Use \x1b[1mgit ascend switch\x1b[0m to level a different stat or view their descriptions."
+        "Use {} to level different stats or view their descriptions.",
+        bold("git ascend switch")
     );
     Ok(())
 }
+
+#[derive(Serialize)]
+pub struct StatsReport {
+    pub version: u32,
+    pub total: LevelReport,
+    pub output: LevelReport,
+    pub pedantry: LevelReport,
+    pub precision: LevelReport,
+    pub knowledge: LevelReport,
+    /// Stats new XP goes into
+    pub active: Vec<ShareReport>,
+    pub prestige: PrestigeReport,
+    pub streak: StreakReport,
+}
+
+impl StatsReport {
+    pub fn level(&self, stat: XpType) -> &LevelReport {
+        match stat {
+            XpType::Total => &self.total,
+            XpType::Output => &self.output,
+            XpType::Pedantry => &self.pedantry,
+            XpType::Precision => &self.precision,
+            XpType::Knowledge => &self.knowledge,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct PrestigeReport {
+    pub count: u32,
+    pub multiplier: f64,
+    /// Total level needed for the next prestige
+    pub next_level: u32,
+}
+
+#[derive(Serialize)]
+pub struct StreakReport {
+    pub current: u32,
+    pub longest: u32,
+    pub multiplier: f64,
+}
+
+/// Everything `main_stats` and `xp_levels` show, for scripts, the dashboard and badges
+pub fn stats_report() -> Result<StatsReport> {
+    let xp = read_xp()?;
+    let prestige = read_prestige()?;
+    let streak = streak(&read_history()?, Local::now().date_naive());
+    Ok(StatsReport {
+        version: SCHEMA_VERSION,
+        total: LevelReport::new(xp.total, XpType::Total),
+        output: LevelReport::new(xp.output, XpType::Output),
+        pedantry: LevelReport::new(xp.pedantry, XpType::Pedantry),
+        precision: LevelReport::new(xp.precision, XpType::Precision),
+        knowledge: LevelReport::new(xp.knowledge, XpType::Knowledge),
+        active: allocation_report(&read_allocation()?),
+        prestige: PrestigeReport {
+            count: prestige,
+            multiplier: prestige_multiplier(prestige),
+            next_level: next_threshold(prestige),
+        },
+        streak: StreakReport {
+            current: streak.current,
+            longest: streak.longest,
+            multiplier: streak_multiplier(streak.current),
+        },
+    })
+}
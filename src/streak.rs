@@ -0,0 +1,230 @@
+use crate::config::config;
+use crate::state::CommitRecord;
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreakConfig {
+    /// Only count Monday to Friday, weekends neither extend nor break a streak
+    pub weekdays_only: bool,
+    /// Days that can be missed without breaking a streak
+    pub grace_days: u32,
+    /// Multiplier added for every day of the current streak after the first
+    pub bonus_per_day: f64,
+    /// Highest multiplier a streak can give
+    pub max_multiplier: f64,
+}
+
+impl Default for StreakConfig {
+    fn default() -> Self {
+        StreakConfig {
+            weekdays_only: false,
+            grace_days: 0,
+            bonus_per_day: 0.01,
+            max_multiplier: 1.5,
+        }
+    }
+}
+
+impl StreakConfig {
+    pub fn validate(&self) -> Result<(), (String, String)> {
+        if !(self.bonus_per_day.is_finite() && self.bonus_per_day >= 0.0) {
+            return Err((
+                "streak.bonus_per_day".to_string(),
+                "must not be negative".to_string(),
+            ));
+        }
+        if !(self.max_multiplier.is_finite() && self.max_multiplier >= 1.0) {
+            return Err((
+                "streak.max_multiplier".to_string(),
+                "must be at least 1".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Streak {
+    pub current: u32,
+    pub longest: u32,
+}
+
+/// Current and longest streaks as of `today`, using each commit's own time zone
+pub fn streak(history: &[CommitRecord], today: NaiveDate) -> Streak {
+    streak_of_days(
+        history.iter().map(|r| r.local_date()),
+        today,
+        &config().streak,
+    )
+}
+
+/// Length of the streak a commit made on `day` extends
+pub fn streak_with(history: &[CommitRecord], day: NaiveDate) -> u32 {
+    streak_on(
+        history.iter().map(|r| r.local_date()),
+        day,
+        &config().streak,
+    )
+}
+
+/// Length of the streak running on `day`, ignoring days after it such as other repositories'
+/// newer commits awarded first by a sync
+fn streak_on(days: impl Iterator<Item = NaiveDate>, day: NaiveDate, rules: &StreakConfig) -> u32 {
+    let days = days.filter(|d| *d <= day).chain([day]);
+    streak_of_days(days, day, rules).current
+}
+
+pub fn streak_multiplier(days: u32) -> f64 {
+    let streak = &config().streak;
+    let bonus = days.saturating_sub(1) as f64 * streak.bonus_per_day;
+    (1.0 + bonus).min(streak.max_multiplier)
+}
+
+fn streak_of_days(
+    days: impl Iterator<Item = NaiveDate>,
+    today: NaiveDate,
+    rules: &StreakConfig,
+) -> Streak {
+    let days: BTreeSet<NaiveDate> = days.filter(|d| counts(*d, rules)).collect();
+    let mut result = Streak::default();
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for &day in &days {
+        run = match previous {
+            Some(p) if continues(p, day, rules) => run + 1,
+            _ => 1,
+        };
+        result.longest = result.longest.max(run);
+        previous = Some(day);
+    }
+    if let Some(last) = previous
+        && (last >= today || continues(last, today, rules))
+    {
+        result.current = run;
+    }
+    result
+}
+
+/// Whether a streak whose last commit was on `last` still runs on `today`
+pub fn alive(last: NaiveDate, today: NaiveDate) -> bool {
+    last >= today || continues(last, today, &config().streak)
+}
+
+/// Whether a day should count towards a streak at all
+fn counts(day: NaiveDate, rules: &StreakConfig) -> bool {
+    !rules.weekdays_only || day.weekday().number_from_monday() <= 5
+}
+
+/// Whether a streak running on `from` is still alive on `to`
+fn continues(from: NaiveDate, to: NaiveDate, rules: &StreakConfig) -> bool {
+    let mut missed = 0;
+    let mut day = from;
+    while let Some(next) = day.succ_opt() {
+        if next >= to {
+            return true;
+        }
+        if counts(next, rules) {
+            missed += 1;
+            if missed > rules.grace_days {
+                return false;
+            }
+        }
+        day = next;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, month, day).unwrap()
+    }
+
+    #[test]
+    fn counts_consecutive_days() {
+        let rules = StreakConfig::default();
+        let days = [date(9, 1), date(9, 2), date(9, 3), date(9, 5), date(9, 6)];
+        let streak = streak_of_days(days.into_iter(), date(9, 6), &rules);
+        assert_eq!(
+            streak,
+            Streak {
+                current: 2,
+                longest: 3
+            }
+        );
+        let streak = streak_of_days(days.into_iter(), date(9, 8), &rules);
+        assert_eq!(
+            streak,
+            Streak {
+                current: 0,
+                longest: 3
+            }
+        );
+    }
+
+    #[test]
+    fn grace_days_bridge_gaps() {
+        let rules = StreakConfig {
+            grace_days: 1,
+            ..StreakConfig::default()
+        };
+        assert!(continues(date(9, 1), date(9, 3), &rules));
+        assert!(!continues(date(9, 1), date(9, 4), &rules));
+        let days = [date(9, 1), date(9, 3), date(9, 5)];
+        let streak = streak_of_days(days.into_iter(), date(9, 6), &rules);
+        assert_eq!(
+            streak,
+            Streak {
+                current: 3,
+                longest: 3
+            }
+        );
+    }
+
+    #[test]
+    fn weekends_neither_extend_nor_break_weekday_streaks() {
+        let rules = StreakConfig {
+            weekdays_only: true,
+            ..StreakConfig::default()
+        };
+        // Friday the 5th to Monday the 8th of September 2025
+        assert!(continues(date(9, 5), date(9, 8), &rules));
+        assert!(!continues(date(9, 4), date(9, 8), &rules));
+        let days = [date(9, 4), date(9, 5), date(9, 6), date(9, 8)];
+        let streak = streak_of_days(days.into_iter(), date(9, 8), &rules);
+        assert_eq!(
+            streak,
+            Streak {
+                current: 3,
+                longest: 3
+            }
+        );
+    }
+
+    #[test]
+    fn days_can_arrive_in_any_order() {
+        let rules = StreakConfig::default();
+        let days = [date(9, 3), date(9, 1), date(9, 2), date(9, 1)];
+        let streak = streak_of_days(days.into_iter(), date(9, 3), &rules);
+        assert_eq!(
+            streak,
+            Streak {
+                current: 3,
+                longest: 3
+            }
+        );
+    }
+
+    #[test]
+    fn later_days_do_not_extend_an_earlier_commit() {
+        let rules = StreakConfig::default();
+        let september = (1..=12).map(|day| date(9, day));
+        assert_eq!(streak_on(september.clone(), date(3, 1), &rules), 1);
+        assert_eq!(streak_on(september, date(9, 13), &rules), 13);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::config::config;
+use crate::scaling::XpType;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::io::{self, IsTerminal};
+use std::sync::OnceLock;
+
+static COLOR: OnceLock<bool> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Color {
+    /// Use the colour of the selected theme
+    Default,
+    /// Leave the bar uncoloured
+    None,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+}
+
+impl Color {
+    fn sgr(self) -> Option<u8> {
+        match self {
+            Color::Default | Color::None => None,
+            Color::Red => Some(31),
+            Color::Green => Some(32),
+            Color::Yellow => Some(33),
+            Color::Blue => Some(34),
+            Color::Magenta => Some(35),
+            Color::Cyan => Some(36),
+            Color::White => Some(37),
+            Color::BrightRed => Some(91),
+            Color::BrightGreen => Some(92),
+            Color::BrightYellow => Some(93),
+            Color::BrightBlue => Some(94),
+            Color::BrightMagenta => Some(95),
+            Color::BrightCyan => Some(96),
+        }
+    }
+
+    /// Colour for SVG and HTML output
+    pub fn hex(self) -> Option<&'static str> {
+        match self {
+            Color::Default | Color::None => None,
+            Color::Red => Some("#cd3131"),
+            Color::Green => Some("#0dbc79"),
+            Color::Yellow => Some("#e5e510"),
+            Color::Blue => Some("#2472c8"),
+            Color::Magenta => Some("#bc3fbc"),
+            Color::Cyan => Some("#11a8cd"),
+            Color::White => Some("#e5e5e5"),
+            Color::BrightRed => Some("#f14c4c"),
+            Color::BrightGreen => Some("#23d18b"),
+            Color::BrightYellow => Some("#f5f543"),
+            Color::BrightBlue => Some("#3b8eea"),
+            Color::BrightMagenta => Some("#d670d6"),
+            Color::BrightCyan => Some("#29b8db"),
+        }
+    }
+
+    pub fn to_ratatui(self) -> Option<ratatui::style::Color> {
+        use ratatui::style::Color as Tui;
+        match self {
+            Color::Default | Color::None => None,
+            Color::Red => Some(Tui::Red),
+            Color::Green => Some(Tui::Green),
+            Color::Yellow => Some(Tui::Yellow),
+            Color::Blue => Some(Tui::Blue),
+            Color::Magenta => Some(Tui::Magenta),
+            Color::Cyan => Some(Tui::Cyan),
+            Color::White => Some(Tui::Gray),
+            Color::BrightRed => Some(Tui::LightRed),
+            Color::BrightGreen => Some(Tui::LightGreen),
+            Color::BrightYellow => Some(Tui::LightYellow),
+            Color::BrightBlue => Some(Tui::LightBlue),
+            Color::BrightMagenta => Some(Tui::LightMagenta),
+            Color::BrightCyan => Some(Tui::LightCyan),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemeName {
+    /// Uncoloured bars
+    Classic,
+    Vivid,
+    Ocean,
+    Ember,
+}
+
+impl ThemeName {
+    /// Colours of the Total, Output, Pedantry, Precision and Knowledge bars
+    fn colors(self) -> [Color; 5] {
+        match self {
+            ThemeName::Classic => [Color::None; 5],
+            ThemeName::Vivid => [
+                Color::BrightCyan,
+                Color::BrightGreen,
+                Color::BrightRed,
+                Color::BrightYellow,
+                Color::BrightMagenta,
+            ],
+            ThemeName::Ocean => [
+                Color::Cyan,
+                Color::BrightBlue,
+                Color::Blue,
+                Color::BrightCyan,
+                Color::Magenta,
+            ],
+            ThemeName::Ember => [
+                Color::BrightYellow,
+                Color::Red,
+                Color::BrightRed,
+                Color::Yellow,
+                Color::Magenta,
+            ],
+        }
+    }
+}
+
+/// Colours of the progress bars, a stat set to `default` uses the theme's colour
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub name: ThemeName,
+    pub total: Color,
+    pub output: Color,
+    pub pedantry: Color,
+    pub precision: Color,
+    pub knowledge: Color,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            name: ThemeName::Classic,
+            total: Color::Default,
+            output: Color::Default,
+            pedantry: Color::Default,
+            precision: Color::Default,
+            knowledge: Color::Default,
+        }
+    }
+}
+
+/// Colour of a stat's progress bar
+pub fn bar_color(stat: XpType) -> Color {
+    let theme = &config().theme;
+    let (configured, idx) = match stat {
+        XpType::Total => (theme.total, 0),
+        XpType::Output => (theme.output, 1),
+        XpType::Pedantry => (theme.pedantry, 2),
+        XpType::Precision => (theme.precision, 3),
+        XpType::Knowledge => (theme.knowledge, 4),
+    };
+    match configured {
+        Color::Default => theme.name.colors()[idx],
+        color => color,
+    }
+}
+
+/// Whether stdout is a terminal a person is watching
+pub fn interactive() -> bool {
+    io::stdout().is_terminal()
+}
+
+/// Whether escape sequences may be printed, following NO_COLOR and CLICOLOR conventions
+pub fn color_enabled() -> bool {
+    *COLOR.get_or_init(|| {
+        let set = |name: &str| env::var_os(name).filter(|v| !v.is_empty());
+        if set("NO_COLOR").is_some() {
+            return false;
+        }
+        if set("CLICOLOR_FORCE").is_some_and(|v| v != "0") {
+            return true;
+        }
+        if set("CLICOLOR").is_some_and(|v| v == "0") {
+            return false;
+        }
+        interactive()
+    })
+}
+
+fn sgr(text: &str, code: u8) -> String {
+    if color_enabled() && !text.is_empty() {
+        format!("\x1b[{code}m{text}\x1b[0m")
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn bold(text: &str) -> String {
+    sgr(text, 1)
+}
+
+pub fn dim(text: &str) -> String {
+    sgr(text, 2)
+}
+
+pub fn paint(text: &str, color: Color) -> String {
+    match color.sgr() {
+        Some(code) => sgr(text, code),
+        None => text.to_string(),
+    }
+}
+
+/// Pad text to a width, not counting escape sequences
+pub fn pad(text: &str, width: usize) -> String {
+    let mut visible = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            'm' if in_escape => in_escape = false,
+            _ if !in_escape => visible += 1,
+            _ => {}
+        }
+    }
+    format!("{text}{}", " ".repeat(width.saturating_sub(visible)))
+}